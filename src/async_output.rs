@@ -0,0 +1,115 @@
+use crate::errors::ViewError;
+use crate::line_ending_writer::LineEndingWriter;
+use crate::line_prefix_writer::LinePrefixWriter;
+use crate::{
+    is_buffered_output, is_stream_done, write_bunyan_line, write_output_with_state,
+    LoggerOutputConfig, StreamState,
+};
+use std::io::{self, Write};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Writes the passed bytes to the async writer and flushes it.
+///
+/// # Arguments
+/// * `writer` - AsyncWrite implementation to output data to
+/// * `bytes` - rendered output to write
+///
+async fn write_bytes<W: AsyncWrite + Unpin>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(bytes).await?;
+    writer.flush().await
+}
+
+/// Writes all of the lines read from an async reader in the configured output format. Each
+/// line is rendered into a buffer the same way `write_bunyan_output` renders it and the
+/// buffer is then written asynchronously. Outputs that group, reorder, collect, count or
+/// summarize lines (`group_by`, `merge_window`, `count_only`, `latency_summary` and the JSON
+/// array format) are written once the whole input has been read, so the whole input is held
+/// in memory.
+///
+/// # Arguments
+/// * `writer` - AsyncWrite implementation to output data to
+/// * `reader` - AsyncBufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the output format
+///
+/// # Errors
+///
+/// Returns `ViewError::IoError` if reading the input or writing the output fails. In `fail_fast`
+/// mode, returns the error of the first line that can't be parsed or rendered. No more input
+/// is read after that line.
+///
+pub async fn write_bunyan_output_async<W, R>(
+    writer: &mut W,
+    mut reader: R,
+    output_config: &LoggerOutputConfig,
+) -> Result<(), ViewError>
+where
+    W: AsyncWrite + Unpin,
+    R: AsyncBufRead + Unpin,
+{
+    if is_buffered_output(output_config) {
+        let mut input: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut input).await?;
+
+        let mut output: Vec<u8> = Vec::new();
+        let state = StreamState::new(output_config);
+        let result = write_output_with_state(&mut output, input.as_slice(), output_config, state);
+        write_bytes(writer, &output).await?;
+
+        return result.map_err(ViewError::from);
+    }
+
+    let mut line_no: usize = 0;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut rendered: Vec<u8> = Vec::new();
+    let mut converted: Vec<u8> = Vec::new();
+    let mut state = StreamState::new(output_config);
+    let mut rendered_lines: usize = 0;
+    let mut has_pending_newline = false;
+
+    while !is_stream_done(rendered_lines, output_config, &state) {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer).await? == 0 {
+            break;
+        }
+        line_no += 1;
+
+        rendered.clear();
+        let is_rendered = match &output_config.line_prefix {
+            Some(prefix) => write_bunyan_line(
+                &mut LinePrefixWriter::new(&mut rendered, prefix),
+                &buffer,
+                line_no,
+                output_config,
+                &mut state,
+            ),
+            None => write_bunyan_line(&mut rendered, &buffer, line_no, output_config, &mut state),
+        };
+
+        if is_rendered {
+            rendered_lines += 1;
+        }
+
+        if rendered.is_empty() {
+            continue;
+        }
+
+        // The line ending the output so far is held back until more output follows it
+        if has_pending_newline {
+            write_bytes(writer, output_config.line_ending.as_bytes()).await?;
+        }
+
+        has_pending_newline = output_config.no_trailing_newline && rendered.ends_with(b"\n");
+        let end = rendered.len() - usize::from(has_pending_newline);
+
+        converted.clear();
+        let mut line_ending_writer =
+            LineEndingWriter::new(&mut converted, output_config.line_ending);
+        line_ending_writer.write_all(&rendered[..end])?;
+        write_bytes(writer, &converted).await?;
+    }
+
+    match state.error {
+        Some(error) => Err(ViewError::from(error)),
+        None => Ok(()),
+    }
+}
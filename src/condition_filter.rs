@@ -1,10 +1,213 @@
 use core::fmt;
+use std::ops::Not;
 
 use quick_js::Context;
+use serde_json::Value;
+
+/// JavaScript function implementing the `contains` operator. Arrays match if they have
+/// the value as an element, strings match if they have the value as a substring.
+const CONTAINS_FUNCTION: &str = "function __bunyanContains(target, value) {
+    if (Array.isArray(target)) {
+        return target.indexOf(value) !== -1;
+    }
+    if (typeof target === 'string' && typeof value === 'string') {
+        return target.indexOf(value) !== -1;
+    }
+    return false;
+}";
+
+/// JavaScript function returning a copy of a log record with its string values (nested ones
+/// included) in lower case, which case insensitive conditions are evaluated against.
+const LOWERCASE_FUNCTION: &str = "function __bunyanLowercase(value) {
+    if (typeof value === 'string') {
+        return value.toLowerCase();
+    }
+    if (Array.isArray(value)) {
+        return value.map(__bunyanLowercase);
+    }
+    if (value !== null && typeof value === 'object') {
+        var copy = {};
+        for (var key in value) {
+            copy[key] = __bunyanLowercase(value[key]);
+        }
+        return copy;
+    }
+    return value;
+}";
+
+/// Operator testing array membership or substrings, eg `tags contains "urgent"`
+const CONTAINS_OPERATOR: &str = " contains ";
 
 pub struct ConditionFilter {
     context: Context,
     condition: String,
+    /// Condition rewritten as plain JavaScript
+    expression: String,
+    field_path: Option<String>,
+    /// Whether string values are compared regardless of their case
+    is_case_insensitive: bool,
+}
+
+/// Returns a JavaScript context in which conditions can be evaluated.
+fn new_context() -> Context {
+    let context = Context::new().unwrap();
+    context.eval(CONTAINS_FUNCTION).unwrap();
+    context.eval(LOWERCASE_FUNCTION).unwrap();
+    context
+}
+
+/// Returns the passed condition with each `field contains value` operation rewritten as
+/// a call to the JavaScript function implementing the operator. Fields may be written
+/// with or without the leading `this.`, and values are string, number or boolean literals.
+///
+/// # Arguments
+/// * `condition` - condition as written by the user
+///
+fn rewrite_contains(condition: &str) -> String {
+    let is_path_char = |c: char| c.is_alphanumeric() || c == '_' || c == '$' || c == '.';
+    let mut expression = String::new();
+    let mut rest = condition;
+
+    while let Some(position) = find_unquoted(rest, CONTAINS_OPERATOR) {
+        let before = &rest[..position];
+        let after = rest[position + CONTAINS_OPERATOR.len()..].trim_start();
+
+        let field_start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_path_char(*c))
+            .last()
+            .map_or(before.len(), |(index, _)| index);
+        let field = &before[field_start..];
+
+        let value_len = match after.chars().next() {
+            Some(quote @ ('"' | '\'')) => after[1..].find(quote).map_or(after.len(), |end| end + 2),
+            _ => after
+                .find(|c: char| !(is_path_char(c) || c == '-'))
+                .unwrap_or(after.len()),
+        };
+
+        // Leave the text as is when either operand is missing
+        if field.is_empty() || value_len == 0 {
+            expression.push_str(&rest[..position + CONTAINS_OPERATOR.len()]);
+            rest = &rest[position + CONTAINS_OPERATOR.len()..];
+            continue;
+        }
+
+        let target = if field.starts_with("this.") {
+            field.to_string()
+        } else {
+            format!("this.{}", field)
+        };
+
+        expression.push_str(&before[..field_start]);
+        expression.push_str(&format!(
+            "__bunyanContains({}, {})",
+            target,
+            &after[..value_len]
+        ));
+        rest = &after[value_len..];
+    }
+
+    expression.push_str(rest);
+    expression
+}
+
+/// Returns the byte index of the first occurrence of `pattern` in the passed expression that
+/// isn't within a string literal.
+///
+/// # Arguments
+/// * `expression` - JavaScript expression
+/// * `pattern` - text to search for
+///
+fn find_unquoted(expression: &str, pattern: &str) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut is_escaped = false;
+
+    for (index, c) in expression.char_indices() {
+        match quote {
+            Some(_) if is_escaped => is_escaped = false,
+            Some(_) if c == '\\' => is_escaped = true,
+            Some(open) => {
+                if c == open {
+                    quote = None;
+                }
+            }
+            None => {
+                if matches!(c, '"' | '\'' | '`') {
+                    quote = Some(c);
+                } else if expression[index..].starts_with(pattern) {
+                    return Some(index);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the passed condition with the contents of the string literals compared to values
+/// of the line in lower case, ie the operands of comparison operators and the values of
+/// `contains`. Other literals, such as the keys of bracket property accesses, are kept as is.
+///
+/// # Arguments
+/// * `condition` - condition as written by the user
+///
+fn lowercase_compared_literals(condition: &str) -> String {
+    let mut lowercased = String::with_capacity(condition.len());
+    let mut rest = condition;
+
+    while let Some(start) = rest.find(['"', '\'', '`']) {
+        let quote = rest[start..].chars().next().unwrap_or('"');
+        let mut is_escaped = false;
+        let end = rest[start + 1..]
+            .char_indices()
+            .find(|(_, c)| {
+                let is_closing = !is_escaped && *c == quote;
+                is_escaped = !is_escaped && *c == '\\';
+                is_closing
+            })
+            .map_or(rest.len(), |(index, _)| start + 1 + index + 1);
+
+        lowercased.push_str(&rest[..start]);
+
+        let before = lowercased.trim_end();
+        let after = rest[end..].trim_start();
+        let is_compared = before.ends_with(['=', '<', '>'])
+            || before.ends_with(CONTAINS_OPERATOR.trim_end())
+            || after.starts_with(['=', '!', '<', '>']);
+
+        if is_compared {
+            lowercased.push_str(&rest[start..end].to_lowercase());
+        } else {
+            lowercased.push_str(&rest[start..end]);
+        }
+        rest = &rest[end..];
+    }
+
+    lowercased.push_str(rest);
+    lowercased
+}
+
+/// Returns the dot-delimited path of the first field of `this` referenced by the passed
+/// condition (eg `req.method` for `this.req.method == "GET"`).
+///
+/// # Arguments
+/// * `condition` - JavaScript condition evaluated against log lines
+///
+fn referenced_field_path(condition: &str) -> Option<String> {
+    let start = condition.find("this.")? + "this.".len();
+    let path: String = condition[start..]
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$' || *c == '.')
+        .collect();
+    let path = path.trim_end_matches('.');
+
+    if path.is_empty() {
+        None
+    } else {
+        Some(path.to_string())
+    }
 }
 
 impl ConditionFilter {
@@ -12,18 +215,66 @@ impl ConditionFilter {
     where
         S: Into<String>,
     {
+        let condition = condition.into();
+        let expression = rewrite_contains(&condition);
+
+        Self {
+            context: new_context(),
+            field_path: referenced_field_path(&expression),
+            condition,
+            expression,
+            is_case_insensitive: false,
+        }
+    }
+
+    /// Returns the filter comparing strings regardless of their case, so that
+    /// `this.component == "DB"` matches `"db"` and `msg contains "Timeout"` matches
+    /// `"request timeout"`. The string literals compared by the condition and the string
+    /// values of the lines are compared in lower case, while property keys keep their case.
+    pub fn case_insensitive(self) -> Self {
         Self {
-            context: Context::new().unwrap(),
-            condition: condition.into(),
+            expression: rewrite_contains(&lowercase_compared_literals(&self.condition)),
+            is_case_insensitive: true,
+            ..self
         }
     }
+
     pub fn filter(&self, line: &str) -> bool {
+        let record = if self.is_case_insensitive {
+            format!("__bunyanLowercase({line})")
+        } else {
+            line.to_string()
+        };
+
         self.context
             .eval_as::<bool>(
-                format!("(function (){{return ({})}}).call({line})", self.condition).as_str(),
+                format!(
+                    "(function (){{return ({})}}).call({record})",
+                    self.expression
+                )
+                .as_str(),
             )
             .unwrap()
     }
+
+    /// Returns the value of the field evaluated by the condition when the passed line
+    /// matches it. `None` is returned when the line doesn't match, or when the condition
+    /// doesn't reference a field of the line that is present.
+    ///
+    /// # Arguments
+    /// * `line` - parsed log line to evaluate the condition against
+    ///
+    pub fn matched_value(&self, line: &Value) -> Option<Value> {
+        let path = self.field_path.as_ref()?;
+
+        if !self.filter(&line.to_string()) {
+            return None;
+        }
+
+        path.split('.')
+            .try_fold(line, |value, key| value.get(key))
+            .cloned()
+    }
 }
 
 impl fmt::Debug for ConditionFilter {
@@ -32,11 +283,158 @@ impl fmt::Debug for ConditionFilter {
     }
 }
 
+/// Negates a condition filter, so that `!filter` matches the lines `filter` doesn't match.
+/// Negated filters are conditions like any other, so they can be negated again.
+impl Not for ConditionFilter {
+    type Output = ConditionFilter;
+
+    fn not(self) -> ConditionFilter {
+        Self {
+            context: self.context,
+            condition: format!("!({})", self.condition),
+            expression: format!("!({})", self.expression),
+            field_path: self.field_path,
+            is_case_insensitive: self.is_case_insensitive,
+        }
+    }
+}
+
 impl Clone for ConditionFilter {
     fn clone(&self) -> Self {
         Self {
-            context: Context::new().unwrap(),
+            context: new_context(),
             condition: self.condition.clone(),
+            expression: self.expression.clone(),
+            field_path: self.field_path.clone(),
+            is_case_insensitive: self.is_case_insensitive,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn matched_value_returns_evaluated_field_of_matching_line() {
+        let filter = ConditionFilter::new(r#"this.req.method == "POST""#);
+        let line = json!({"msg": "My message", "req": {"method": "POST", "url": "/"}});
+
+        assert_eq!(filter.matched_value(&line), Some(json!("POST")));
+    }
+
+    #[test]
+    fn matched_value_is_none_for_non_matching_line() {
+        let filter = ConditionFilter::new("this.level > 30");
+        let line = json!({"msg": "My message", "level": 30});
+
+        assert_eq!(filter.matched_value(&line), None);
+    }
+
+    #[test]
+    fn contains_matches_array_element() {
+        let filter = ConditionFilter::new(r#"tags contains "urgent""#);
+
+        assert!(filter.filter(r#"{"tags":["low","urgent"]}"#));
+        assert!(!filter.filter(r#"{"tags":["low","urgentish"]}"#));
+    }
+
+    #[test]
+    fn contains_matches_substring() {
+        let filter = ConditionFilter::new(r#"this.msg contains "time" && this.level >= 40"#);
+
+        assert!(filter.filter(r#"{"msg":"request timeout","level":50}"#));
+        assert!(!filter.filter(r#"{"msg":"request failed","level":50}"#));
+    }
+
+    #[test]
+    fn contains_does_not_match_numbers() {
+        let filter = ConditionFilter::new("pid contains 12");
+
+        assert!(!filter.filter(r#"{"pid":123}"#));
+    }
+
+    #[test]
+    fn contains_within_string_literal_is_left_as_is() {
+        let filter = ConditionFilter::new(r#"this.msg == "x contains y""#);
+
+        assert!(filter.filter(r#"{"msg":"x contains y"}"#));
+        assert!(!filter.filter(r#"{"msg":"x"}"#));
+
+        let filter = ConditionFilter::new(r#"this.msg == 'a " contains b' || tags contains "c""#);
+
+        assert!(filter.filter(r#"{"msg":"a \" contains b"}"#));
+        assert!(filter.filter(r#"{"msg":"","tags":["c"]}"#));
+    }
+
+    #[test]
+    fn negated_filter_inverts_the_inner_filter() {
+        let filter = !ConditionFilter::new(r#"this.component == "db""#);
+
+        assert!(!filter.filter(r#"{"component":"db"}"#));
+        assert!(filter.filter(r#"{"component":"api"}"#));
+        assert_eq!(
+            format!("{:?}", filter),
+            r#"ConditionFilter [`!(this.component == "db")`]"#
+        );
+    }
+
+    #[test]
+    fn double_negation_matches_like_the_inner_filter() {
+        let filter = ConditionFilter::not(!ConditionFilter::new(r#"tags contains "urgent""#));
+
+        assert!(filter.filter(r#"{"tags":["urgent"]}"#));
+        assert!(!filter.filter(r#"{"tags":["low"]}"#));
+    }
+
+    #[test]
+    fn case_insensitive_equality_ignores_case() {
+        let filter = ConditionFilter::new(r#"this.component == "DB""#);
+        assert!(!filter.filter(r#"{"component":"db"}"#));
+
+        let filter = filter.case_insensitive();
+        assert!(filter.filter(r#"{"component":"db"}"#));
+        assert!(filter.filter(r#"{"component":"Db"}"#));
+        assert!(!filter.filter(r#"{"component":"api"}"#));
+    }
+
+    #[test]
+    fn case_insensitive_contains_ignores_case() {
+        let filter = ConditionFilter::new(r#"msg contains "Timeout""#).case_insensitive();
+
+        assert!(filter.filter(r#"{"msg":"REQUEST TIMEOUT"}"#));
+        assert!(!filter.filter(r#"{"msg":"request failed"}"#));
+        assert!((!filter).filter(r#"{"msg":"request failed"}"#));
+    }
+
+    #[test]
+    fn lowercase_compared_literals_keeps_code_and_escapes() {
+        assert_eq!(
+            lowercase_compared_literals(r#"this.Msg == "A \"B\"" || this.X == 'C'"#),
+            r#"this.Msg == "a \"b\"" || this.X == 'c'"#
+        );
+        assert_eq!(
+            lowercase_compared_literals(r#"this.headers["Content-Type"] != "JSON""#),
+            r#"this.headers["Content-Type"] != "json""#
+        );
+    }
+
+    #[test]
+    fn case_insensitive_keeps_bracket_keys() {
+        let filter = ConditionFilter::new(r#"this.headers["Content-Type"] == "Application/JSON""#)
+            .case_insensitive();
+
+        assert!(filter.filter(r#"{"headers":{"Content-Type":"application/json"}}"#));
+        assert!(!filter.filter(r#"{"headers":{"content-type":"application/json"}}"#));
+    }
+
+    #[test]
+    fn referenced_field_path_finds_first_field() {
+        assert_eq!(
+            referenced_field_path(r#"this.req.url == "/" && this.level > 30"#),
+            Some("req.url".to_string())
+        );
+        assert_eq!(referenced_field_path("true"), None);
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Result, Write};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+/// Function that writes its own representation of a parameter value.
+pub type CustomRenderer = dyn Fn(&Value, &mut dyn Write) -> Result<()> + Send + Sync;
+
+/// Map of dot-delimited parameter paths (eg `traceId`, `req.traceId`) to functions that
+/// render the value of the parameter in place of the default rendering.
+///
+#[derive(Clone, Default)]
+pub struct CustomRenderers {
+    renderers: HashMap<String, Arc<CustomRenderer>>,
+}
+
+impl CustomRenderers {
+    pub fn new() -> Self {
+        CustomRenderers::default()
+    }
+
+    /// Associates a renderer with a parameter path, replacing any existing renderer for
+    /// the path.
+    ///
+    /// # Arguments
+    /// * `path` - dot-delimited path of the parameter (eg `traceId` or `req.traceId`)
+    /// * `renderer` - function writing the representation of the parameter value
+    ///
+    pub fn insert<S, F>(&mut self, path: S, renderer: F)
+    where
+        S: Into<String>,
+        F: Fn(&Value, &mut dyn Write) -> Result<()> + Send + Sync + 'static,
+    {
+        self.renderers.insert(path.into(), Arc::new(renderer));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.renderers.is_empty()
+    }
+
+    /// Returns the renderer for the passed parameter path.
+    ///
+    /// # Arguments
+    /// * `path` - dot-delimited path of the parameter being rendered
+    ///
+    pub fn get(&self, path: &str) -> Option<&CustomRenderer> {
+        self.renderers.get(path).map(|renderer| renderer.as_ref())
+    }
+
+    /// Renders the passed value to a string with the renderer associated with `path`.
+    /// `None` is returned if there is no renderer for the path or if the renderer failed.
+    ///
+    /// # Arguments
+    /// * `path` - dot-delimited path of the parameter being rendered
+    /// * `value` - value of the parameter
+    ///
+    pub fn render(&self, path: &str, value: &Value) -> Option<String> {
+        let renderer = self.get(path)?;
+        let mut rendered: Vec<u8> = Vec::new();
+
+        match renderer(value, &mut rendered) {
+            Ok(_) => Some(String::from_utf8_lossy(&rendered).into_owned()),
+            Err(_) => None,
+        }
+    }
+}
+
+impl fmt::Debug for CustomRenderers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.renderers.keys()).finish()
+    }
+}
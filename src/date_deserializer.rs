@@ -1,6 +1,7 @@
 use chrono::{DateTime, SecondsFormat, Utc};
 use serde::de::Error as DeError;
 use serde::{Deserialize, Deserializer, Serializer};
+use serde_json::Value;
 use std::error::Error as StdError;
 use std::fmt;
 
@@ -10,14 +11,15 @@ use std::fmt;
 /// timestamp as a RFC2822 compatible timestamp.
 ///
 pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
-    let deserialize_result = Deserialize::deserialize(deserializer);
+    // Owned so that timestamps with escapes, or read from a `Value`, can be deserialized
+    let deserialize_result: Result<String, D::Error> = Deserialize::deserialize(deserializer);
     #[allow(clippy::question_mark)]
     if let Err(err) = deserialize_result {
         return Err(err);
     }
 
     let time = deserialize_result.unwrap();
-    let parsed = parse_timestamp(time);
+    let parsed = parse_timestamp(&time);
 
     match parsed {
         Ok(timestamp) => Ok(timestamp),
@@ -28,6 +30,29 @@ pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTim
     }
 }
 
+/// Deserializes an optional timestamp string. See `deserialize` for the supported formats.
+///
+pub fn deserialize_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    deserialize(deserializer).map(Some)
+}
+
+/// Parses a timestamp found in a JSON value that was not directly deserialized.
+///
+/// # Errors
+///
+/// Returns an error message if the value is not a string or can't be parsed as a timestamp.
+///
+pub fn parse_json_timestamp(value: &Value) -> Result<DateTime<Utc>, String> {
+    match value.as_str() {
+        Some(time) => {
+            parse_timestamp(time).map_err(|_| format!("Unable to parse timestamp [{}]", time))
+        }
+        None => Err(format!("Unable to parse timestamp [{}]", value)),
+    }
+}
+
 pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -116,14 +141,10 @@ mod tests {
     ) {
         match parse_result {
             Ok(parsed) => {
-                assert_eq!(parsed.timestamp_nanos(), expected_as_epoch);
+                assert_eq!(parsed.timestamp_nanos_opt(), Some(expected_as_epoch));
             }
             Err(error) => {
-                eprintln!(
-                    "Error parsing [{}]: {}",
-                    error.timestamp_input,
-                    error.to_string()
-                );
+                eprintln!("Error parsing [{}]: {}", error.timestamp_input, error);
                 panic!("{}", error)
             }
         }
@@ -4,7 +4,7 @@ use std::fmt::Arguments;
 use std::io::{Error, Write};
 
 /// Textual divider between log sections
-const DIVIDER: &str = "--\n";
+pub(crate) const DIVIDER: &str = "--\n";
 
 /// Struct whose purpose is to wrap any instance that implements the `Write` trait in order to
 /// inject periodical dividers into the wrapped `Write` implementation.
@@ -16,7 +16,7 @@ pub struct DividerWriter<'a, W: Write> {
 }
 
 impl<'a, W: Write> DividerWriter<'a, W> {
-    pub fn new(inner: &'a mut W, divider_written: bool) -> DividerWriter<W> {
+    pub fn new(inner: &'a mut W, divider_written: bool) -> DividerWriter<'a, W> {
         DividerWriter {
             inner,
             divider_written,
@@ -0,0 +1,156 @@
+use serde::de::{
+    DeserializeOwned, DeserializeSeed, Deserializer, Error, MapAccess, SeqAccess, Visitor,
+};
+use serde_json::map::Map;
+use serde_json::{Error as SerdeError, Value};
+use std::fmt;
+
+/// Policy deciding which value is kept when a JSON object contains the same key more than
+/// once. Node-bunyan parses lines with `JSON.parse` which keeps the last value.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateKeys {
+    /// The value of the first occurrence of a key is kept
+    FirstWins,
+    /// The value of the last occurrence of a key is kept, like `JSON.parse` does
+    #[default]
+    LastWins,
+}
+
+/// Deserializes `T` from the passed JSON, resolving the duplicate keys of all of its objects
+/// (nested ones included) according to `policy`.
+///
+/// When keeping the last value, the line is first deserialized directly because it is only
+/// invalid for `T` when a field deserialized explicitly appears twice. Errors are reported
+/// from the direct deserialization so that they keep their position in the line.
+///
+/// # Arguments
+/// * `line` - raw bytes of the JSON to deserialize
+/// * `policy` - policy deciding which value of a duplicate key is kept
+///
+pub fn from_slice<T: DeserializeOwned>(
+    line: &[u8],
+    policy: DuplicateKeys,
+) -> Result<T, SerdeError> {
+    let direct_result = match policy {
+        DuplicateKeys::LastWins => serde_json::from_slice(line),
+        DuplicateKeys::FirstWins => return from_resolved_value(line, policy),
+    };
+
+    match direct_result {
+        Err(e) if e.is_data() => from_resolved_value(line, policy).map_err(|_| e),
+        result => result,
+    }
+}
+
+/// Deserializes `T` from the passed JSON once it has been read into a `Value` with its
+/// duplicate keys resolved.
+fn from_resolved_value<T: DeserializeOwned>(
+    line: &[u8],
+    policy: DuplicateKeys,
+) -> Result<T, SerdeError> {
+    let mut deserializer = serde_json::Deserializer::from_slice(line);
+    let value = ValueSeed(policy).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+
+    serde_json::from_value(value)
+}
+
+/// Seed deserializing a JSON `Value` whose objects resolve duplicate keys with its policy
+#[derive(Clone, Copy)]
+struct ValueSeed(DuplicateKeys);
+
+impl<'de> DeserializeSeed<'de> for ValueSeed {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ValueSeed {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E: Error>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E: Error>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_u64<E: Error>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_f64<E: Error>(self, value: f64) -> Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_str<E: Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E: Error>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+
+        while let Some(value) = seq.next_element_seed(self)? {
+            values.push(value);
+        }
+
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut values = Map::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(self)?;
+
+            match self.0 {
+                DuplicateKeys::FirstWins => {
+                    values.entry(key).or_insert(value);
+                }
+                DuplicateKeys::LastWins => {
+                    values.insert(key, value);
+                }
+            }
+        }
+
+        Ok(Value::Object(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_duplicate_keys_follow_policy() {
+        let line = br#"{"a":{"b":1,"b":2},"a":{"b":3,"b":4}}"#;
+
+        let first: Value = from_slice(line, DuplicateKeys::FirstWins).unwrap();
+        assert_eq!(first, serde_json::json!({"a": {"b": 1}}));
+
+        let last: Value = from_slice(line, DuplicateKeys::LastWins).unwrap();
+        assert_eq!(last, serde_json::json!({"a": {"b": 4}}));
+    }
+
+    #[test]
+    fn invalid_json_is_an_error() {
+        let result: Result<Value, SerdeError> = from_slice(b"{\"a\":", DuplicateKeys::FirstWins);
+        assert!(result.is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use std::error::Error as StdError;
+use std::io;
 use std::num::ParseIntError;
 use std::{fmt, str};
 
@@ -125,6 +126,17 @@ impl Error {
     pub fn column(&self) -> Option<usize> {
         self.inner.column
     }
+
+    /// Returns the description of the error without the position serde appends to it
+    /// (eg "at line 1 column 5"), which is relative to the line rather than the input.
+    pub fn reason(&self) -> String {
+        let description = self.to_string();
+
+        match description.split_once(" at line ") {
+            Some((reason, _)) => reason.to_string(),
+            None => description,
+        }
+    }
 }
 
 impl fmt::Debug for Error {
@@ -176,3 +188,88 @@ impl From<::serde_json::Error> for Kind {
         Kind::Json(error)
     }
 }
+
+/// Error returned to library users, which can be matched on without depending on the
+/// internals of the JSON parser.
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum ViewError {
+    /// The line isn't valid JSON or doesn't have the structure of a bunyan log line
+    ParseError {
+        message: String,
+        line_no: usize,
+        column: Option<usize>,
+    },
+    /// Reading the input or writing the output failed
+    IoError(io::Error),
+    /// The line is a bunyan log line but is rejected by the configured validation (eg it is
+    /// missing a required field) or can't be rendered. `line_no` is 0 when the configuration
+    /// itself can't be used.
+    ValidationError { message: String, line_no: usize },
+}
+
+impl ViewError {
+    /// Returns the error of a parsed line that couldn't be rendered.
+    ///
+    /// # Arguments
+    /// * `error` - error returned while rendering the line
+    /// * `line_no` - number of the line, starting at 1
+    ///
+    pub(crate) fn from_render_error(error: BunyanLogParseError, line_no: usize) -> ViewError {
+        ViewError::ValidationError {
+            message: error.to_string(),
+            line_no,
+        }
+    }
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ViewError::ParseError {
+                ref message,
+                line_no,
+                ..
+            } => write!(f, "{} on line {}", message, line_no),
+            ViewError::IoError(ref e) => e.fmt(f),
+            ViewError::ValidationError {
+                ref message,
+                line_no,
+            } => write!(f, "{} on line {}", message, line_no),
+        }
+    }
+}
+
+impl StdError for ViewError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            ViewError::IoError(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for ViewError {
+    fn from(error: Error) -> ViewError {
+        let message = error.reason();
+        let line_no = error.line_no();
+        let column = error.column();
+
+        match error.inner.kind {
+            Kind::Json(e) if e.is_io() => ViewError::IoError(io::Error::from(e)),
+            Kind::Json(_) => ViewError::ParseError {
+                message,
+                line_no,
+                column,
+            },
+            Kind::BunyanLogParse(_) => ViewError::ValidationError { message, line_no },
+        }
+    }
+}
+
+impl From<io::Error> for ViewError {
+    #[inline]
+    fn from(error: io::Error) -> ViewError {
+        ViewError::IoError(error)
+    }
+}
@@ -0,0 +1,148 @@
+use colored::{Color, ColoredString, Colorize};
+use std::collections::HashMap;
+
+/// Color and text attributes applied to a single rendered field.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldStyle {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub dimmed: bool,
+}
+
+impl FieldStyle {
+    /// Creates a new style that renders a field with the passed foreground color.
+    pub fn color(color: Color) -> Self {
+        FieldStyle {
+            color: Some(color),
+            ..FieldStyle::default()
+        }
+    }
+
+    /// Returns a copy of this style that also renders in bold.
+    pub fn bold(self) -> Self {
+        FieldStyle { bold: true, ..self }
+    }
+
+    /// Returns a copy of this style that also renders in italics.
+    pub fn italic(self) -> Self {
+        FieldStyle {
+            italic: true,
+            ..self
+        }
+    }
+
+    /// Returns a copy of this style that also renders underlined.
+    pub fn underline(self) -> Self {
+        FieldStyle {
+            underline: true,
+            ..self
+        }
+    }
+
+    /// Returns a copy of this style that also renders dimmed.
+    pub fn dimmed(self) -> Self {
+        FieldStyle {
+            dimmed: true,
+            ..self
+        }
+    }
+
+    /// Applies the style to the passed text.
+    ///
+    /// # Arguments
+    /// * `text` - text to colorize
+    ///
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let mut painted = text.normal();
+
+        if let Some(color) = self.color {
+            painted = painted.color(color);
+        }
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.italic {
+            painted = painted.italic();
+        }
+        if self.underline {
+            painted = painted.underline();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+
+        painted
+    }
+}
+
+/// Map of dot-delimited field paths (eg `msg`, `req.url`) to the style used when rendering
+/// the field. A path ending in `.*` (eg `err.*`) applies to every field under that record.
+///
+#[derive(Debug, Clone, Default)]
+pub struct FieldStyles {
+    styles: HashMap<String, FieldStyle>,
+}
+
+impl FieldStyles {
+    pub fn new() -> Self {
+        FieldStyles::default()
+    }
+
+    /// Associates a style with a field path, replacing any existing style for the path.
+    ///
+    /// # Arguments
+    /// * `path` - dot-delimited path of the field (eg `req.url` or `err.*`)
+    /// * `style` - style to apply to the field
+    ///
+    pub fn insert<S: Into<String>>(&mut self, path: S, style: FieldStyle) {
+        self.styles.insert(path.into(), style);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.styles.is_empty()
+    }
+
+    /// Returns the style for the passed field path. An exact match takes precedence over
+    /// a wildcard match, and the wildcard of the nearest parent takes precedence over that
+    /// of a more distant one.
+    ///
+    /// # Arguments
+    /// * `path` - dot-delimited path of the field being rendered
+    ///
+    pub fn get(&self, path: &str) -> Option<&FieldStyle> {
+        if self.styles.is_empty() {
+            return None;
+        }
+
+        if let Some(style) = self.styles.get(path) {
+            return Some(style);
+        }
+
+        let mut parent = path;
+        while let Some(pos) = parent.rfind('.') {
+            parent = &parent[..pos];
+            if let Some(style) = self.styles.get(&format!("{}.*", parent)) {
+                return Some(style);
+            }
+        }
+
+        None
+    }
+
+    /// Applies the style associated with `path` to `text`, or returns `text` unchanged
+    /// if no style is associated with the path.
+    ///
+    /// # Arguments
+    /// * `path` - dot-delimited path of the field being rendered
+    /// * `text` - text to colorize
+    ///
+    pub fn paint(&self, path: &str, text: &str) -> String {
+        match self.get(path) {
+            Some(style) => style.paint(text).to_string(),
+            None => text.to_string(),
+        }
+    }
+}
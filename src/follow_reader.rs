@@ -0,0 +1,59 @@
+use std::io::{Read, Result};
+use std::thread;
+use std::time::Duration;
+
+/// Default interval to wait before checking for more data after reaching the end of input
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Struct whose purpose is to wrap any instance that implements the `Read` trait in order to
+/// keep reading from it after it has reached its end, in the manner of `tail -f`.
+///
+/// The wrapped reader is never seeked. Reading always begins from the current position of the
+/// reader (the start for a newly opened file) and when no data is available, the reader is
+/// polled again after the poll interval. This allows following both regular files and
+/// non-seekable streams such as named pipes and process substitutions:
+///
+/// * For a regular file, all of the data already in the file is read and then any data
+///   appended to it is read as it is written.
+/// * For a pipe, only the data written to it is read. When all writers have closed the
+///   pipe, it continues to be polled so that data from a new writer of a named pipe is
+///   read. A process substitution can't be reopened, so it will be polled until the
+///   process is interrupted.
+///
+pub struct FollowReader<R: Read> {
+    inner: R,
+    poll_interval: Duration,
+}
+
+impl<R: Read> FollowReader<R> {
+    pub fn new(inner: R) -> FollowReader<R> {
+        FollowReader::with_poll_interval(inner, DEFAULT_POLL_INTERVAL)
+    }
+
+    pub fn with_poll_interval(inner: R, poll_interval: Duration) -> FollowReader<R> {
+        FollowReader {
+            inner,
+            poll_interval,
+        }
+    }
+}
+
+/// Read from the inner `Read` instance, waiting for data instead of reporting the end of input.
+///
+impl<R: Read> Read for FollowReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let read = self.inner.read(buf)?;
+
+            if read > 0 {
+                return Ok(read);
+            }
+
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
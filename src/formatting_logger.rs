@@ -1,10 +1,13 @@
 use crate::divider_writer::DividerWriter;
 use crate::errors::{BunyanLogParseError, ParseIntFromJsonError, ParseResult};
 use crate::BASE_INDENT_SIZE;
-use crate::{BunyanLine, LogLevel, Logger, LoggerOutputConfig};
+use crate::{BunyanLine, FieldStyle, LogLevel, Logger, LoggerOutputConfig};
 
+use std::borrow::Cow;
 use std::io::Write;
 
+use serde::Serialize;
+
 use httpstatus::StatusCode;
 
 use serde_json::map::Map;
@@ -12,21 +15,20 @@ use serde_json::Value;
 
 use colored::*;
 
-use chrono::{Local, SecondsFormat};
+use chrono::{DateTime, Local, SecondsFormat, TimeDelta, Utc};
 
-/// Maximum characters for a string value in the extra parameters section
-const LONG_LINE_SIZE: usize = 50;
 /// Reserved keywords for requests records
-const REQ_RESERVED: [&str; 6] = [
+const REQ_RESERVED: [&str; 7] = [
     "method",
     "url",
     "httpVersion",
     "body",
     "headers",
     "trailers",
+    "socket",
 ];
 /// Reserved keywords for client requests records
-const CLIENT_REQ_RESERVED: [&str; 8] = [
+const CLIENT_REQ_RESERVED: [&str; 9] = [
     "method",
     "url",
     "httpVersion",
@@ -35,24 +37,95 @@ const CLIENT_REQ_RESERVED: [&str; 8] = [
     "trailers",
     "address",
     "port",
+    "socket",
 ];
 /// Reserved keywords for responses records
-const RES_RESERVED: [&str; 6] = [
+const RES_RESERVED: [&str; 7] = [
     "statusCode",
     "header",
     "headers",
     "trailer",
     "body",
     "trailer",
+    "socket",
 ];
 /// Reserved keywords for client responses records
-const CLIENT_RES_RESERVED: [&str; 5] = ["statusCode", "body", "header", "headers", "trailer"];
+const CLIENT_RES_RESERVED: [&str; 6] = [
+    "statusCode",
+    "body",
+    "header",
+    "headers",
+    "trailer",
+    "socket",
+];
+/// Keywords of requests records written in their summary line
+const REQ_SUMMARY: [&str; 3] = ["method", "url", "httpVersion"];
+/// Keywords of responses records written in their status line
+const RES_SUMMARY: [&str; 1] = ["statusCode"];
 /// Reserved keywords for error records
 const ERR_RESERVED: [&str; 3] = ["message", "name", "stack"];
+/// OpenTelemetry trace context keys, written in their own style
+const TRACE_CONTEXT_KEYS: [&str; 4] = ["trace_id", "traceId", "span_id", "spanId"];
+/// Number of hex digits trace context IDs are truncated to when `truncate_trace_ids` is set
+const TRUNCATED_TRACE_ID_LEN: usize = 8;
 /// Reserved keywords for the `other` map in `BunyanLine`
 const GENERAL_RESERVED: [&str; 5] = ["req", "client_req", "res", "client_res", "err"];
 /// Default assumed HTTP version
 const DEFAULT_HTTP_VERSION: &str = "1.1";
+/// Width of relative times when `fixed_time_width` is set, enough for offsets up to a day
+const RELATIVE_TIME_WIDTH: usize = 10;
+/// Width of epoch times when `fixed_time_width` is set, enough for milliseconds until 2286
+const EPOCH_TIME_WIDTH: usize = 13;
+/// Time displayed for lines without a time when `time_fallback` is `TimeFallback::Unknown`
+const UNKNOWN_TIME: &str = "?";
+/// Colors assigned to components when `color_by_component` is set. Red is left out so that
+/// components aren't mistaken for errors.
+const COMPONENT_PALETTE: [Color; 10] = [
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+    Color::BrightCyan,
+];
+
+/// Moves the keys of the configured context object into the extra parameters of the log line
+/// so that they are rendered like any other parameter. Keys that are already parameters of
+/// the line are left in the context object, which is only kept when some keys remain.
+///
+/// # Arguments
+///
+/// * `other` - Mutable map containing the extra parameters of the log line
+/// * `output_config` - Configuration defining the name of the context object
+///
+fn hoist_context_fields(other: &mut Map<String, Value>, output_config: &LoggerOutputConfig) {
+    let field = match output_config.context_field.as_deref() {
+        Some(field) if matches!(other.get(field), Some(Value::Object(_))) => field,
+        _ => return,
+    };
+
+    let context = match other.remove(field) {
+        Some(Value::Object(context)) => context,
+        _ => return,
+    };
+
+    let mut remaining = Map::new();
+    for (key, value) in context {
+        if other.contains_key(&key) {
+            remaining.insert(key, value);
+        } else {
+            other.insert(key, value);
+        }
+    }
+
+    if !remaining.is_empty() {
+        other.insert(field.to_string(), Value::Object(remaining));
+    }
+}
 
 /// Writes the src information of the log line if it is present.
 ///
@@ -60,20 +133,38 @@ const DEFAULT_HTTP_VERSION: &str = "1.1";
 ///
 /// * `writer` - Write implementation to output data to
 /// * `other` - Mutable map containing JSON optional JSON data. Keys will be removed as processed.
+/// * `output_config` - Configuration defining whether the file is written as a hyperlink
 ///
-fn write_src<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
+fn write_src<W: Write>(
+    writer: &mut W,
+    other: &mut Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) {
     if let Some(ref src) = other.remove("src") {
         match src {
             Value::Object(map) => {
                 // We only display the src information if [src.file] is present
                 if let Some(ref file) = map.get("file") {
-                    w!(writer, "{}", " (".green());
-                    w!(writer, "{}", string_or_value!(file).green());
+                    let file = string_or_value!(file);
+                    let mut location = file.green().to_string();
 
                     if let Some(ref line) = map.get("line") {
-                        w!(writer, ":{}", string_or_value!(line).green());
+                        location.push_str(&format!(":{}", string_or_value!(line).green()));
                     }
 
+                    // Hyperlinks are escape sequences, so they are only written with colors
+                    if output_config.src_as_hyperlink
+                        && colored::control::SHOULD_COLORIZE.should_colorize()
+                    {
+                        location = format!(
+                            "\u{1b}]8;;file://{}\u{1b}\\{}\u{1b}]8;;\u{1b}\\",
+                            file, location
+                        );
+                    }
+
+                    w!(writer, "{}", " (".green());
+                    w!(writer, "{}", location);
+
                     if let Some(ref func) = map.get("func") {
                         w!(writer, "{}", " in ");
                         w!(writer, "{}", string_or_value!(func).bright_green());
@@ -88,6 +179,116 @@ fn write_src<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
     }
 }
 
+/// Writes the bunyan schema version of the log line if it is present and requested.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `version` - value of the [v] field of the log line
+/// * `output_config` - Configuration defining whether the version is written
+///
+fn write_version<W: Write>(
+    writer: &mut W,
+    version: Option<u8>,
+    output_config: &LoggerOutputConfig,
+) {
+    if let Some(version) = version.filter(|_| output_config.show_version) {
+        w!(writer, " (v{})", version);
+    }
+}
+
+/// Returns the passed text with each tab replaced by `tab_width` spaces, or the text
+/// unchanged if no tab width is configured.
+///
+/// # Arguments
+/// * `text` - string value to expand the tabs of
+/// * `output_config` - Configuration defining the tab width
+///
+fn expand_tabs<'a>(text: &'a str, output_config: &LoggerOutputConfig) -> Cow<'a, str> {
+    match output_config.tab_width {
+        Some(tab_width) if text.contains('\t') => text.replace('\t', &" ".repeat(tab_width)).into(),
+        _ => text.into(),
+    }
+}
+
+/// Returns true for the control characters replaced by `escape_control_chars`. Line feeds
+/// and tabs are kept because they are handled by the multiline and tab width logic.
+fn is_escaped_control_char(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+/// Returns the passed text with each control character (eg NUL, carriage return or the
+/// escape character of ANSI sequences) replaced by its `\xNN` escape if
+/// `escape_control_chars` is set, or the text unchanged otherwise.
+///
+/// # Arguments
+/// * `text` - string value to escape the control characters of
+/// * `output_config` - Configuration defining whether control characters are escaped
+///
+fn escape_control_chars<'a>(text: &'a str, output_config: &LoggerOutputConfig) -> Cow<'a, str> {
+    if !output_config.escape_control_chars || !text.chars().any(is_escaped_control_char) {
+        return text.into();
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if is_escaped_control_char(c) {
+            escaped.push_str(&format!("\\x{:02x}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+
+    escaped.into()
+}
+
+/// Returns the passed message with each run of whitespace (eg line breaks and tabs) replaced
+/// by a single space if `collapse_msg_whitespace` is set, or the message unchanged otherwise.
+///
+/// # Arguments
+/// * `msg` - message of the log line
+/// * `output_config` - Configuration defining whether whitespace is collapsed
+///
+fn collapse_msg_whitespace<'a>(msg: &'a str, output_config: &LoggerOutputConfig) -> Cow<'a, str> {
+    if !output_config.collapse_msg_whitespace {
+        return msg.into();
+    }
+
+    let mut collapsed = String::with_capacity(msg.len());
+    let mut is_whitespace_run = false;
+
+    for c in msg.chars() {
+        if !c.is_whitespace() {
+            collapsed.push(c);
+        } else if !is_whitespace_run {
+            collapsed.push(' ');
+        }
+        is_whitespace_run = c.is_whitespace();
+    }
+
+    collapsed.into()
+}
+
+/// Returns the passed value as a pretty printed JSON string indented by the nested object
+/// indent configured in `output_config`.
+///
+/// # Arguments
+/// * `value` - Value to be converted to a pretty printed string
+/// * `output_config` - Configuration defining the indent of nested objects
+///
+fn to_string_pretty(value: &Value, output_config: &LoggerOutputConfig) -> String {
+    let indent = output_config.object_indent();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut pretty: Vec<u8> = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut pretty, formatter);
+
+    match value.serialize(&mut serializer) {
+        Ok(_) => String::from_utf8(pretty).unwrap_or_else(|_| "[malformed]".to_string()),
+        Err(_) => "[malformed]".to_string(),
+    }
+}
+
 /// Writes all of the extra parameters to the top line of output by iterating through the `others`
 /// map provided.
 ///
@@ -95,11 +296,13 @@ fn write_src<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
 /// * `writer` - Write implementation to output data to
 /// * `other` - Map containing all non-explicitly deserialized keys and values
 /// * `details` - Mutable vector containing strings to be written as output later
+/// * `output_config` - Configuration defining styles and indentation of parameters
 ///
 fn write_all_extra_params<W: Write>(
     writer: &mut W,
     other: &mut Map<String, Value>,
     details: &mut Vec<String>,
+    output_config: &LoggerOutputConfig,
 ) {
     /// Returns the passed value as a pretty printed JSON string with indents.
     ///
@@ -107,10 +310,15 @@ fn write_all_extra_params<W: Write>(
     /// * `key` - Key associated with value being processed
     /// * `value` - Value to be converted to a pretty printed string
     /// * `caller_option` - Optional name of top-level record (eg `req`, `res`, `err`, etc)
+    /// * `output_config` - Configuration defining the indent of nested objects
     ///
-    fn detail_pretty_print(key: &str, value: &Value, caller_option: Option<&str>) -> String {
-        let pretty =
-            ::serde_json::to_string_pretty(value).unwrap_or_else(|_| "[malformed]".to_string());
+    fn detail_pretty_print(
+        key: &str,
+        value: &Value,
+        caller_option: Option<&str>,
+        output_config: &LoggerOutputConfig,
+    ) -> String {
+        let pretty = to_string_pretty(value, output_config);
 
         match caller_option {
             Some(caller) => format!("{}.{}: {}", caller, key, pretty),
@@ -119,13 +327,43 @@ fn write_all_extra_params<W: Write>(
     }
 
     /// Returns true if the given JSON value is a JSON string and it has a newline character or it
-    /// is longer than the LONG_LINE_SIZE (50 characterS).
+    /// is longer than the configured `inline_value_threshold` (50 characters by default). The
+    /// length of the string isn't taken into account in `wide` mode.
     ///
     /// # Arguments
     ///
     /// * `text` - string to test to see if it qualifies for multiline output
-    fn is_multiline_string(text: &str) -> bool {
-        text.contains('\n') || text.len() > LONG_LINE_SIZE
+    /// * `output_config` - Configuration defining the longest string displayed inline
+    fn is_multiline_string(text: &str, output_config: &LoggerOutputConfig) -> bool {
+        text.contains('\n')
+            || (!output_config.wide && text.len() > output_config.inline_value_threshold)
+    }
+
+    /// Returns the detail displaying a multiline string value. The value follows its label
+    /// unless it spans multiple lines and `multiline_key_on_own_line` is set, in which case
+    /// the label is written on its own line followed by the indented value.
+    ///
+    /// # Arguments
+    /// * `label` - Label of the value (eg `key` or `caller.key`)
+    /// * `text` - Multiline string value
+    /// * `output_config` - Configuration defining the layout and indentation of the value
+    ///
+    fn multiline_string_detail(
+        label: &str,
+        text: &str,
+        output_config: &LoggerOutputConfig,
+    ) -> String {
+        if output_config.multiline_key_on_own_line && text.contains('\n') {
+            let indent = output_config.object_indent();
+            let block: Vec<String> = text
+                .lines()
+                .map(|line| format!("{}{}", indent, line))
+                .collect();
+
+            format!("{}:\n{}", label, block.join("\n"))
+        } else {
+            format!("{}: {}", label, text)
+        }
     }
 
     /// Returns the passed value as a string optionally with enclosing quotes. If
@@ -145,6 +383,57 @@ fn write_all_extra_params<W: Write>(
         string_or_value!(value)
     }
 
+    /// Returns the passed array rendered inline (eg `[a, b, c]`) if `inline_scalar_arrays`
+    /// is set, all of its elements are single line scalars and the rendered array isn't
+    /// longer than `inline_value_threshold`. Otherwise, `None` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `array` - elements of the JSON array to render
+    /// * `output_config` - Configuration defining whether and how long arrays are inlined
+    fn inline_scalar_array(array: &[Value], output_config: &LoggerOutputConfig) -> Option<String> {
+        if !output_config.inline_scalar_arrays {
+            return None;
+        }
+
+        let is_scalar = |value: &Value| match value {
+            Value::String(text) => !text.contains('\n'),
+            Value::Array(_) | Value::Object(_) => false,
+            _ => true,
+        };
+
+        if !array.iter().all(is_scalar) {
+            return None;
+        }
+
+        let elements: Vec<String> = array.iter().map(quoteify).collect();
+        let text = format!("[{}]", elements.join(", "));
+
+        if text.len() > output_config.inline_value_threshold {
+            None
+        } else {
+            Some(text)
+        }
+    }
+
+    /// Returns the passed object rendered as compact single line JSON (eg `{"a":1,"b":2}`) if
+    /// it isn't longer than `compact_small_objects`. Otherwise, `None` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `object` - JSON object to render
+    /// * `output_config` - Configuration defining how long compact objects can be
+    fn compact_small_object(object: &Value, output_config: &LoggerOutputConfig) -> Option<String> {
+        let max_width = output_config.compact_small_objects?;
+        let text = object.to_string();
+
+        if text.len() > max_width {
+            None
+        } else {
+            Some(text)
+        }
+    }
+
     /// Returns an optional string representing the string presentation of an extra parameter. When
     /// a `None` value is returned, the value has been added to the `details` vector.
     ///
@@ -153,51 +442,101 @@ fn write_all_extra_params<W: Write>(
     /// * `value` - Value to be converted to a pretty printed string
     /// * `caller_option` - Optional name of top-level record (eg `req`, `res`, `err`, etc)
     /// * `details` - Mutable vector containing strings to be written as output later
+    /// * `output_config` - Configuration defining the indent of nested objects
     fn stringify(
         key: &str,
         value: &Value,
         caller_option: Option<&str>,
         details: &mut Vec<String>,
+        output_config: &LoggerOutputConfig,
     ) -> Option<String> {
         match value {
             Value::String(text) => {
+                let text = expand_tabs(text, output_config);
+
                 // Add long strings to details
-                if is_multiline_string(text) {
-                    let detail = match caller_option {
-                        Some(caller) => format!("{}.{}: {}", caller, key, text),
-                        None => format!("{}: {}", key, text),
+                if is_multiline_string(&text, output_config) {
+                    let label = match caller_option {
+                        Some(caller) => format!("{}.{}", caller, key),
+                        None => key.to_string(),
                     };
+                    let text = escape_control_chars(&text, output_config);
+                    let detail = multiline_string_detail(&label, &text, output_config);
 
                     details.push(detail);
 
                     None
                 // Wrap strings with spaces in quotation marks
                 } else {
-                    Some(quoteify(value))
+                    let quoted = match text {
+                        Cow::Borrowed(_) => quoteify(value),
+                        Cow::Owned(expanded) => quoteify(&Value::String(expanded)),
+                    };
+                    Some(escape_control_chars(&quoted, output_config).into_owned())
                 }
             }
-            Value::Number(_) => Some(string_or_value!(value)),
-            Value::Bool(_) => Some(string_or_value!(value)),
-            Value::Null => Some(string_or_value!(value)),
+            // Numbers, booleans and nulls are rendered the same way node-bunyan does
+            Value::Number(_) | Value::Bool(_) | Value::Null => Some(quoteify(value)),
             Value::Object(map) => {
                 if map.is_empty() {
                     Some("{}".to_string())
+                } else if let Some(text) = compact_small_object(value, output_config) {
+                    Some(text)
                 } else {
-                    details.push(detail_pretty_print(key, value, caller_option));
+                    details.push(detail_pretty_print(
+                        key,
+                        value,
+                        caller_option,
+                        output_config,
+                    ));
                     None
                 }
             }
             Value::Array(array) => {
                 if array.is_empty() {
                     Some("[]".to_string())
+                } else if let Some(text) = inline_scalar_array(array, output_config) {
+                    Some(text)
                 } else {
-                    details.push(detail_pretty_print(key, value, caller_option));
+                    details.push(detail_pretty_print(
+                        key,
+                        value,
+                        caller_option,
+                        output_config,
+                    ));
                     None
                 }
             }
         }
     }
 
+    /// Returns the `key=value` text of an extra parameter. The whole parameter is painted with
+    /// the style of its field if there is one, otherwise its key and value are painted with
+    /// the configured parameter key and value styles.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - dot-delimited path of the parameter
+    /// * `text` - rendered value of the parameter
+    /// * `output_config` - Configuration defining the styles of the parameter
+    ///
+    fn paint_param(path: &str, text: &str, output_config: &LoggerOutputConfig) -> String {
+        if let Some(style) = output_config.field_styles.get(path) {
+            return style.paint(&format!("{}={}", path, text)).to_string();
+        }
+
+        let paint = |style: &Option<FieldStyle>, text: &str| match style {
+            Some(style) => style.paint(text).to_string(),
+            None => text.to_string(),
+        };
+
+        format!(
+            "{}={}",
+            paint(&output_config.param_key_style, path),
+            paint(&output_config.param_value_style, text)
+        )
+    }
+
     /// Writes the loading open parentheses if `if_first` is true. Otherwise, if
     /// `is_first` is false, then it writes a leading command and space.
     ///
@@ -223,6 +562,7 @@ fn write_all_extra_params<W: Write>(
     /// * `optional_node` - Optional Json object represented as `Value` containing parameters to be processed
     /// * `details` - Mutable vector containing strings to be written as output later
     /// * `exclude` - Closure in which when evaluated is true will exclude a given parameter
+    /// * `output_config` - Configuration defining styles and indentation of parameters
     ///
     fn write_params_for_object<W: Write>(
         writer: &mut W,
@@ -231,49 +571,81 @@ fn write_all_extra_params<W: Write>(
         node_option: Option<&Value>,
         details: &mut Vec<String>,
         exclude: &dyn Fn(&str) -> bool,
+        output_config: &LoggerOutputConfig,
     ) {
         if node_option.is_none() {
             return;
         }
 
         let node = node_option.unwrap();
+        let field_styles = &output_config.field_styles;
 
-        // Display strings, numbers and null values, as-is
-        if caller_option.is_some()
-            && (node.is_string() || node.is_number() || node.is_null() || node.is_boolean())
-        {
-            write_formatting(writer, is_first);
-            w!(writer, "{}={}", caller_option.unwrap(), quoteify(node));
-            return;
+        // Display strings, numbers, booleans and null values, as-is
+        if let Some(caller) = caller_option {
+            if !(node.is_object() || node.is_array()) {
+                write_formatting(writer, is_first);
+                let param = paint_param(caller, &quoteify(node), output_config);
+                w!(writer, "{}", param);
+                return;
+            }
         }
 
         if let Some(caller_option_value) = caller_option {
             if node.is_array() {
-                let value = stringify(caller_option_value, node, None, details);
+                let value = stringify(caller_option_value, node, None, details, output_config);
                 if let Some(text) = value {
                     write_formatting(writer, is_first);
-                    w!(writer, "{}={}\n", caller_option_value, text);
+                    let param = format!("{}={}", caller_option_value, text);
+                    w!(
+                        writer,
+                        "{}\n",
+                        field_styles.paint(caller_option_value, &param)
+                    );
                 }
                 return;
             }
         }
 
         let map = node.as_object().unwrap();
+        let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+
+        if output_config.sort_params {
+            entries.sort_by_key(|(k, _)| *k);
+        }
 
-        for (k, v) in map.iter() {
+        for (k, v) in entries {
             if exclude(k.as_str()) {
                 continue;
             }
 
-            let value: Option<String> = stringify(k, v, caller_option, details);
+            let path = match caller_option {
+                Some(caller) => format!("{}.{}", caller, k),
+                None => k.to_string(),
+            };
+
+            let trace_context_id = match (caller_option, v) {
+                (None, Value::String(id)) if TRACE_CONTEXT_KEYS.contains(&k.as_str()) => Some(id),
+                _ => None,
+            };
+
+            // Custom renderers take precedence over the default rendering of a value
+            let value: Option<String> = match output_config.custom_renderers.render(&path, v) {
+                Some(rendered) => Some(rendered),
+                None => match trace_context_id {
+                    Some(id) => Some(format_trace_context_id(id, output_config)),
+                    None => stringify(k, v, caller_option, details, output_config),
+                },
+            };
 
             if let Some(text) = value {
                 write_formatting(writer, is_first);
 
-                match caller_option {
-                    Some(caller) => w!(writer, "{}.{}={}", caller, k, text),
-                    None => w!(writer, "{}={}", k, text),
-                }
+                // Trace context IDs stand out unless a style was configured for them
+                let param = match (trace_context_id, field_styles.get(&path)) {
+                    (Some(_), None) => format!("{}={}", path, text).magenta().to_string(),
+                    _ => paint_param(&path, &text, output_config),
+                };
+                w!(writer, "{}", param);
             }
         }
     }
@@ -281,9 +653,11 @@ fn write_all_extra_params<W: Write>(
     let mut is_first: bool = true;
 
     // REQUEST ID [req_id] - special case we always write this first for visibility
-    if let Some(req_id) = other.remove("req_id") {
+    let req_id_field = output_config.req_id_field();
+    if let Some(req_id) = other.remove(req_id_field) {
         write_formatting(writer, &mut is_first);
-        w!(writer, "req_id={}", string_or_value!(req_id));
+        let param = paint_param(req_id_field, &string_or_value!(req_id), output_config);
+        w!(writer, "{}", param);
     }
 
     /* Note: based on logic in write_params_for_object, parameters that do not fit
@@ -299,12 +673,18 @@ fn write_all_extra_params<W: Write>(
         Some(&other_value),
         details,
         &|k: &str| GENERAL_RESERVED.contains(&k),
+        output_config,
     );
 
     /* Below, we write out the parameters of all JSON keys that are present in
     GENERAL_RESERVED in order to output any of the contents that need to be
     present in the extra parameters section of the logs. */
 
+    let req_fields = detail_fields("req", output_config);
+    let client_req_fields = detail_fields("client_req", output_config);
+    let res_fields = detail_fields("res", output_config);
+    let client_res_fields = detail_fields("client_res", output_config);
+
     // REQUEST [rec]
     write_params_for_object(
         writer,
@@ -312,7 +692,8 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("req"),
         details,
-        &|k: &str| REQ_RESERVED.contains(&k),
+        &|k: &str| req_fields.contains(&k),
+        output_config,
     );
 
     // CLIENT REQUEST [client_req]
@@ -322,7 +703,8 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("client_req"),
         details,
-        &|k: &str| CLIENT_REQ_RESERVED.contains(&k),
+        &|k: &str| client_req_fields.contains(&k),
+        output_config,
     );
 
     // RESPONSE [res]
@@ -332,7 +714,8 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("res"),
         details,
-        &|k: &str| RES_RESERVED.contains(&k),
+        &|k: &str| res_fields.contains(&k),
+        output_config,
     );
 
     // CLIENT RESPONSE [client_res]
@@ -342,17 +725,21 @@ fn write_all_extra_params<W: Write>(
         &mut is_first,
         other.get("client_res"),
         details,
-        &|k: &str| CLIENT_RES_RESERVED.contains(&k),
+        &|k: &str| client_res_fields.contains(&k),
+        output_config,
     );
 
-    // ERROR INFORMATION [err]
+    // ERROR INFORMATION [err], the message and name of errors without a stack aren't
+    // written with the stack, so they are written with the other fields of the error
+    let is_err_stackless = other.get("err").is_some_and(is_stackless_err);
     write_params_for_object(
         writer,
         Some("err"),
         &mut is_first,
         other.get("err"),
         details,
-        &|k: &str| ERR_RESERVED.contains(&k),
+        &|k: &str| !is_err_stackless && ERR_RESERVED.contains(&k),
+        output_config,
     );
 
     if !is_first {
@@ -360,14 +747,118 @@ fn write_all_extra_params<W: Write>(
     }
 }
 
+/// Returns the keywords of the passed request or response record that aren't displayed in
+/// its summary or details by default.
+///
+/// # Arguments
+///
+/// * `key` - name of the record (eg `req` or `client_res`)
+///
+fn reserved_fields(key: &str) -> &'static [&'static str] {
+    match key {
+        "req" => &REQ_RESERVED,
+        "client_req" => &CLIENT_REQ_RESERVED,
+        "res" => &RES_RESERVED,
+        _ => &CLIENT_RES_RESERVED,
+    }
+}
+
+/// Returns the keys of the passed request or response record that are displayed in the
+/// details of the line rather than as extra parameters, which are its reserved keywords
+/// unless `req_detail_fields` or `res_detail_fields` is configured. The keys written in the
+/// summary or status line of the record are always part of them.
+///
+/// # Arguments
+///
+/// * `key` - name of the record (eg `req` or `client_res`)
+/// * `output_config` - Configuration defining the detail fields of requests and responses
+///
+fn detail_fields<'a>(key: &str, output_config: &'a LoggerOutputConfig) -> Vec<&'a str> {
+    let (configured, summary): (_, &[&str]) = match key {
+        "req" | "client_req" => (&output_config.req_detail_fields, &REQ_SUMMARY),
+        _ => (&output_config.res_detail_fields, &RES_SUMMARY),
+    };
+
+    match configured {
+        Some(fields) => summary
+            .iter()
+            .copied()
+            .chain(fields.iter().map(String::as_str))
+            .collect(),
+        None => reserved_fields(key).to_vec(),
+    }
+}
+
+/// Writes the configured detail fields of a request or response record that aren't one of
+/// its reserved keywords, the same way the body of a request is written.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `key` - name of the record (eg `req` or `client_res`)
+/// * `map` - Mutable map of the record. The written keys will be removed.
+/// * `output_config` - Configuration defining the detail fields of the record
+///
+fn write_custom_details<W: Write>(
+    writer: &mut W,
+    key: &str,
+    map: &mut Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) {
+    for field in detail_fields(key, output_config) {
+        if reserved_fields(key).contains(&field) {
+            continue;
+        }
+
+        if let Some(value) = map.remove(field) {
+            write_body(writer, value, output_config);
+        }
+    }
+}
+
+/// Writes the passed body indented below the request, pretty printing objects and arrays.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `body` - value of the body
+/// * `output_config` - Configuration defining how JSON bodies are printed
+///
+fn write_body<W: Write>(writer: &mut W, body: Value, output_config: &LoggerOutputConfig) {
+    let body = parse_json_body(body, output_config);
+
+    if body.is_object() || body.is_array() {
+        let pretty = to_string_pretty(&body, output_config);
+        for line in pretty.lines() {
+            wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
+        }
+    } else {
+        let body_text = string_or_value!(body);
+        wln!(
+            writer,
+            "{:indent$}{}",
+            "",
+            body_text,
+            indent = BASE_INDENT_SIZE
+        );
+    }
+}
+
 /// Writes the HTTP request information logged for the line.
 ///
 /// # Arguments
 ///
 /// * `writer` - Write implementation to output data to
+/// * `key` - name of the request record (eg `req` or `client_req`)
 /// * `other` - Map containing all non-explicitly deserialized keys and values
+/// * `output_config` - Configuration defining styles and indentation of the request
 ///
-fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>) {
+fn write_req<W: Write>(
+    writer: &mut W,
+    key: &str,
+    other: &mut Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) {
     /// Writes the method, url and HTTP version associated with a request.
     ///
     /// # Arguments
@@ -375,6 +866,8 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
     /// * `writer` - Write implementation to output data to
     /// * `caller_name` - text indicating if we have been invoked from a "req" or "client_req" code path
     /// * `req_map` - Mutable map request data. Keys will be removed as processed.
+    /// * `output_config` - Configuration defining the styles of the method and url and whether
+    ///   the query string of the url is expanded
     ///
     /// # Errors
     ///
@@ -386,12 +879,20 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         writer: &mut W,
         caller: &str,
         req_map: &mut Map<String, Value>,
+        output_config: &LoggerOutputConfig,
     ) -> ParseResult {
+        let field_styles = &output_config.field_styles;
+        // Query parameters written on their own lines after the summary when expanded
+        let mut query_params: Vec<String> = Vec::new();
+
         w!(writer, "{:indent$}", "", indent = BASE_INDENT_SIZE);
 
         if let Some(method) = req_map.remove("method") {
             if let Some(method_text) = method.as_str() {
-                w!(writer, "{} ", method_text.yellow());
+                match field_styles.get(&format!("{}.method", caller)) {
+                    Some(style) => w!(writer, "{} ", style.paint(method_text)),
+                    None => w!(writer, "{} ", method_text.yellow()),
+                }
             } else {
                 return Err(BunyanLogParseError::new(format!(
                     "[{}.method] is not a JSON string",
@@ -406,8 +907,22 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         }
 
         if let Some(url) = req_map.remove("url") {
-            if let Some(url_text) = url.as_str() {
-                w!(writer, "{} ", url_text.bright_blue());
+            if let Some(mut url_text) = url.as_str() {
+                if output_config.expand_query {
+                    if let Some((path, query)) = url_text.split_once('?') {
+                        url_text = path;
+                        query_params = query
+                            .split('&')
+                            .filter(|param| !param.is_empty())
+                            .map(String::from)
+                            .collect();
+                    }
+                }
+
+                match field_styles.get(&format!("{}.url", caller)) {
+                    Some(style) => w!(writer, "{} ", style.paint(url_text)),
+                    None => w!(writer, "{} ", url_text.bright_blue()),
+                }
             } else {
                 return Err(BunyanLogParseError::new(format!(
                     "[{}.url] is not a JSON string",
@@ -439,6 +954,18 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         }
 
         wln!(writer);
+
+        for param in query_params {
+            wln!(
+                writer,
+                "{:indent$}{}{}",
+                "",
+                output_config.object_indent(),
+                param,
+                indent = BASE_INDENT_SIZE
+            );
+        }
+
         Ok(())
     }
 
@@ -456,9 +983,13 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
 
     let req_map = req.as_object_mut().unwrap();
 
+    // Keys that aren't detail fields have already been written as extra parameters
+    let fields = detail_fields(key, output_config);
+    req_map.retain(|k, _| fields.contains(&k.as_str()));
+
     // METHOD, URL, HTTP VERSION
     // If we can't parse a method, URL or Http Version from the request, output in JSON as is
-    if write_req_summary(writer, key, req_map).is_err() {
+    if write_req_summary(writer, key, req_map, output_config).is_err() {
         wln!(writer, "undefined undefined HTTP/1.1");
         return;
     }
@@ -487,30 +1018,42 @@ fn write_req<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         write_headers(writer, &headers);
     }
 
+    // SOCKET
+    write_socket(writer, req_map, output_config);
+
     // HTTP BODY
     if let Some(body) = req_map.remove("body") {
-        if let Some(body_map) = body.as_object() {
-            let pretty = ::serde_json::to_string_pretty(&body_map)
-                .unwrap_or_else(|_| "[malformed]".to_string());
-            for line in pretty.lines() {
-                wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
-            }
-        } else {
-            let body_text = string_or_value!(body);
-            wln!(
-                writer,
-                "{:indent$}{}",
-                "",
-                body_text,
-                indent = BASE_INDENT_SIZE
-            );
-        }
+        write_body(writer, body, output_config);
     }
 
     // HTTP TRAILER HEADERS
     if let Some(trailers) = req_map.remove("trailers") {
         write_headers(writer, &trailers);
     }
+
+    // CONFIGURED DETAIL FIELDS
+    write_custom_details(writer, key, req_map, output_config);
+}
+
+/// Returns the JSON object or array encoded in the passed string body if `pretty_json_bodies`
+/// is set, so that it is pretty printed like an object body. Otherwise, or if the body isn't
+/// a JSON object or array, the body is returned unchanged.
+///
+/// # Arguments
+///
+/// * `body` - value of the body of the request
+/// * `output_config` - Configuration defining whether string bodies are parsed
+///
+fn parse_json_body(body: Value, output_config: &LoggerOutputConfig) -> Value {
+    if let Value::String(text) = &body {
+        if output_config.pretty_json_bodies {
+            if let Ok(parsed @ (Value::Object(_) | Value::Array(_))) = serde_json::from_str(text) {
+                return parsed;
+            }
+        }
+    }
+
+    body
 }
 
 /// Converts the passed JSON value to an unsigned integer converting a numeric string or a
@@ -575,8 +1118,14 @@ fn json_string_or_number_as_u16(val: &Value) -> Result<u16, ParseIntFromJsonErro
 ///
 /// * `writer` - Write implementation to output data to
 /// * `other` - Map containing all non-explicitly deserialized keys and values
+/// * `output_config` - Configuration defining the reason phrases of status codes
 ///
-fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>) {
+fn write_res<W: Write>(
+    writer: &mut W,
+    key: &str,
+    other: &mut Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) {
     /// Searches the passed map for the key `headers` and then `header` returning whichever
     /// is found first and is a valid string or JSON object. Otherwise, `None` is returned.
     fn find_headers(map: &mut Map<String, Value>) -> Option<Value> {
@@ -595,7 +1144,27 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         None
     }
 
-    /// Converts the passed JSON value and writes it out as a HTTP status code.
+    /// Returns true if the response has a `duration` or `responseTime` (in milliseconds, as a
+    /// number or a numeric string) over the configured `slow_response_ms` threshold.
+    fn is_slow_response(map: &Map<String, Value>, output_config: &LoggerOutputConfig) -> bool {
+        let threshold = match output_config.slow_response_ms {
+            Some(threshold) => threshold,
+            None => return false,
+        };
+
+        let duration = ["duration", "responseTime"]
+            .iter()
+            .find_map(|key| match map.get(*key) {
+                Some(Value::Number(number)) => number.as_f64(),
+                Some(Value::String(string)) => string.trim().parse::<f64>().ok(),
+                _ => None,
+            });
+
+        duration.is_some_and(|duration| duration > threshold as f64)
+    }
+
+    /// Converts the passed JSON value and writes it out as a HTTP status code. Slow responses
+    /// have their whole status line written in bold red.
     ///
     /// # Errors
     ///
@@ -605,12 +1174,11 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         writer: &mut W,
         optional_code: Option<Value>,
         option_http_version: Option<&str>,
+        is_slow: bool,
+        output_config: &LoggerOutputConfig,
     ) {
         let numeric_status_code = if let Some(json_value) = optional_code {
-            match json_string_or_number_as_u16(&json_value) {
-                Err(_) => None,
-                Ok(number) => Some(number),
-            }
+            json_string_or_number_as_u16(&json_value).ok()
         } else {
             None
         };
@@ -618,12 +1186,17 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
         if let Some(code) = numeric_status_code {
             let http_version = option_http_version.unwrap_or(DEFAULT_HTTP_VERSION);
             let http_status = format!("HTTP/{}", http_version);
+            let http_status = if is_slow {
+                http_status.red().bold()
+            } else {
+                http_status.cyan()
+            };
 
             w!(
                 writer,
                 "{:indent$}{}",
                 "",
-                http_status.cyan(),
+                http_status,
                 indent = BASE_INDENT_SIZE
             );
 
@@ -641,9 +1214,18 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
                 "white"
             };
 
-            let status_code = StatusCode::from(code);
-            let response_status = format!(" {} {}", code, status_code.reason_phrase());
-            w!(writer, "{}", response_status.color(color));
+            // Configured reason phrases take precedence over the standard ones
+            let reason_phrase = match output_config.status_phrases.get(&code) {
+                Some(phrase) => phrase.clone(),
+                None => StatusCode::from(code).reason_phrase().to_string(),
+            };
+            let response_status = format!(" {} {}", code, reason_phrase);
+            let response_status = if is_slow {
+                response_status.red().bold()
+            } else {
+                response_status.color(color)
+            };
+            w!(writer, "{}", response_status);
             wln!(writer);
         }
     }
@@ -662,18 +1244,29 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
     }
 
     let res_map = res.as_object_mut().unwrap();
+    let is_slow = is_slow_response(res_map, output_config);
+
+    // Keys that aren't detail fields have already been written as extra parameters
+    let fields = detail_fields(key, output_config);
+    res_map.retain(|k, _| fields.contains(&k.as_str()));
 
     // HEADERS
     if let Some(ref headers) = find_headers(res_map) {
         match headers {
             Value::String(headers_str) => {
-                let http_version = if headers_str.starts_with("HTTP/") {
-                    Some(&headers_str[5..8])
-                } else {
-                    None
-                };
-
-                write_res_status_code(writer, res_map.remove("statusCode"), http_version);
+                // The version is read from the status line (eg `HTTP/1.1 200 OK`) when the
+                // header block starts with one, it may be truncated or missing entirely
+                let http_version = headers_str
+                    .strip_prefix("HTTP/")
+                    .and_then(|status_line| status_line.split_whitespace().next());
+
+                write_res_status_code(
+                    writer,
+                    res_map.remove("statusCode"),
+                    http_version,
+                    is_slow,
+                    output_config,
+                );
 
                 let lines = headers_str.lines();
 
@@ -685,16 +1278,31 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
                 }
             }
             Value::Object(_) => {
-                write_res_status_code(writer, res_map.remove("statusCode"), None);
+                write_res_status_code(
+                    writer,
+                    res_map.remove("statusCode"),
+                    None,
+                    is_slow,
+                    output_config,
+                );
                 write_headers(writer, headers);
             }
             _ => (),
         }
     // Attempt to write out the status code line, even if we don't have headers
     } else {
-        write_res_status_code(writer, res_map.remove("statusCode"), None);
+        write_res_status_code(
+            writer,
+            res_map.remove("statusCode"),
+            None,
+            is_slow,
+            output_config,
+        );
     }
 
+    // SOCKET
+    write_socket(writer, res_map, output_config);
+
     // BODY
     if let Some(body_val) = res_map.remove("body") {
         let body = string_or_value!(body_val);
@@ -706,6 +1314,45 @@ fn write_res<W: Write>(writer: &mut W, key: &str, other: &mut Map<String, Value>
             }
         }
     }
+
+    // CONFIGURED DETAIL FIELDS
+    write_custom_details(writer, key, res_map, output_config);
+}
+
+/// Writes the `socket` sub-object of a request or response record (eg its remote address and
+/// the number of bytes read) below the headers.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `map` - Mutable map of the request or response record. The socket key will be removed.
+/// * `output_config` - Configuration defining the indentation of the socket object
+///
+fn write_socket<W: Write>(
+    writer: &mut W,
+    map: &mut Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) {
+    if let Some(socket) = map.remove("socket") {
+        let text = if socket.is_object() || socket.is_array() {
+            to_string_pretty(&socket, output_config)
+        } else {
+            string_or_value!(socket)
+        };
+
+        w!(writer, "{:indent$}socket:", "", indent = BASE_INDENT_SIZE);
+
+        let mut is_first = true;
+
+        for line in text.lines() {
+            if is_first {
+                wln!(writer, " {}", line);
+                is_first = false;
+            } else {
+                wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
+            }
+        }
+    }
 }
 
 /// Writes the contents of `headers` or `header` in the passed map.
@@ -729,16 +1376,24 @@ fn write_headers<W: Write>(writer: &mut W, headers: &Value) {
         }
         Value::Object(headers_map) => {
             for (k, v) in headers_map.iter() {
-                w!(writer, "{:indent$}{}:", "", k, indent = BASE_INDENT_SIZE);
+                // Multi-value headers (eg fetch style headers) are written once per value
+                let values: Vec<&Value> = match v {
+                    Value::Array(values) => values.iter().collect(),
+                    _ => vec![v],
+                };
+
+                for value in values {
+                    w!(writer, "{:indent$}{}:", "", k, indent = BASE_INDENT_SIZE);
 
-                let mut is_first = true;
+                    let mut is_first = true;
 
-                for line in string_or_value!(v).lines() {
-                    if is_first {
-                        wln!(writer, " {}", line);
-                        is_first = false;
-                    } else {
-                        wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
+                    for line in string_or_value!(value).lines() {
+                        if is_first {
+                            wln!(writer, " {}", line);
+                            is_first = false;
+                        } else {
+                            wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
+                        }
                     }
                 }
             }
@@ -753,8 +1408,14 @@ fn write_headers<W: Write>(writer: &mut W, headers: &Value) {
 ///
 /// * `writer` - Write implementation to output data to
 /// * `other` - Map containing all non-explicitly deserialized keys and values
+/// * `output_config` - Configuration defining styles and indentation of the error
 ///
-fn write_err<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
+fn write_err<W: Write>(
+    writer: &mut W,
+    other: &mut Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) {
+    let field_styles = &output_config.field_styles;
     let err_option = other.remove("err");
 
     if err_option.is_none() {
@@ -772,24 +1433,30 @@ fn write_err<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
     if let Some(ref stack_val) = err_map.remove("stack") {
         match stack_val {
             Value::String(stack_str) => {
-                for line in stack_str.lines() {
-                    wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
-                }
-            }
-            Value::Array(stack_array) => {
-                for line in stack_array.iter() {
+                if let Some(header) = err_header(err_map, stack_str) {
+                    let header = field_styles.paint("err.message", &header);
                     wln!(
                         writer,
                         "{:indent$}{}",
                         "",
-                        string_or_value!(line),
+                        header,
                         indent = BASE_INDENT_SIZE
                     );
                 }
+
+                for line in stack_str.lines() {
+                    let line = field_styles.paint("err.stack", line);
+                    wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
+                }
+            }
+            Value::Array(stack_array) => {
+                for line in stack_array.iter() {
+                    let line = field_styles.paint("err.stack", &string_or_value!(line));
+                    wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
+                }
             }
             _ => {
-                let pretty = ::serde_json::to_string_pretty(&stack_val)
-                    .unwrap_or_else(|_| "[malformed]".to_string());
+                let pretty = to_string_pretty(stack_val, output_config);
                 for line in pretty.lines() {
                     wln!(writer, "{:indent$}{}", "", line, indent = BASE_INDENT_SIZE);
                 }
@@ -798,6 +1465,60 @@ fn write_err<W: Write>(writer: &mut W, other: &mut Map<String, Value>) {
     }
 }
 
+/// Returns the passed OpenTelemetry trace or span ID, truncated to its first hex digits if
+/// `truncate_trace_ids` is set.
+///
+/// # Arguments
+///
+/// * `id` - Hex encoded trace or span ID
+/// * `output_config` - Configuration defining whether IDs are truncated
+///
+fn format_trace_context_id(id: &str, output_config: &LoggerOutputConfig) -> String {
+    if output_config.truncate_trace_ids {
+        id.chars().take(TRUNCATED_TRACE_ID_LEN).collect()
+    } else {
+        id.to_string()
+    }
+}
+
+/// Returns true if the passed error is an object with fields but no stack. All of the fields
+/// of such errors are written as extra parameters.
+///
+/// # Arguments
+///
+/// * `err` - Value of the `err` field of a line
+///
+fn is_stackless_err(err: &Value) -> bool {
+    err.as_object()
+        .is_some_and(|err_map| !err_map.is_empty() && !err_map.contains_key("stack"))
+}
+
+/// Returns the `name: message` header of an error whose stack doesn't start with its message.
+/// Most runtimes already start stacks with that header, in which case `None` is returned so
+/// that the message isn't written twice.
+///
+/// # Arguments
+///
+/// * `err_map` - Map containing the fields of the error, without its stack
+/// * `stack` - Stack of the error
+///
+fn err_header(err_map: &Map<String, Value>, stack: &str) -> Option<String> {
+    let message = match err_map.get("message") {
+        Some(Value::String(message)) if !message.is_empty() => message,
+        _ => return None,
+    };
+
+    let first_line = stack.lines().next().unwrap_or_default();
+    if first_line.contains(message.as_str()) {
+        return None;
+    }
+
+    match err_map.get("name") {
+        Some(Value::String(name)) if !name.is_empty() => Some(format!("{}: {}", name, message)),
+        _ => Some(message.to_string()),
+    }
+}
+
 /// Writes the accumulated "details parameters" that do not properly fit in the "extra parameters"
 /// section of the output.
 ///
@@ -824,19 +1545,344 @@ fn write_details<W: Write>(divider_writer: &mut DividerWriter<W>, details: Vec<S
     }
 }
 
+/// Returns the label configured for a custom log level. The label of the closest lower
+/// code applies, unless a standard level lies between that code and the level, so that
+/// each label covers a range of custom codes (eg 25 to 29 for a label at 25).
+///
+/// # Arguments
+///
+/// * `code` - numeric value of the custom log level
+/// * `output_config` - Configuration defining the labels of custom levels
+///
+fn custom_level_label(code: u16, output_config: &LoggerOutputConfig) -> Option<&str> {
+    let (start, label) = output_config.level_labels.range(..=code).next_back()?;
+    let crosses_standard_level = (*start..=code)
+        .skip(1)
+        .any(|level| !matches!(LogLevel::from(level), LogLevel::OTHER(_)));
+
+    if crosses_standard_level {
+        None
+    } else {
+        Some(label)
+    }
+}
+
+/// Returns the standard level whose band the passed level code falls in, which is the
+/// closest lower standard level (eg WARN for 45). Codes below TRACE are in no band.
+///
+/// # Arguments
+///
+/// * `code` - numeric value of the log level
+///
+fn standard_level_band(code: u16) -> Option<LogLevel> {
+    match code {
+        0..=9 => None,
+        10..=59 => Some(LogLevel::from(code - code % 10)),
+        _ => Some(LogLevel::FATAL),
+    }
+}
+
+/// Returns the passed level text colored like the band of the level. Custom levels are
+/// colored like the closest lower standard level, unless a style configured in
+/// `level_styles` starts closer to the level.
+///
+/// # Arguments
+///
+/// * `level` - log level to display
+/// * `level_text` - text displaying the level
+/// * `output_config` - Configuration defining the styles of the levels
+///
+fn colorize_log_level(
+    level: &LogLevel,
+    level_text: String,
+    output_config: &LoggerOutputConfig,
+) -> String {
+    let code = level.as_u16();
+    let band = standard_level_band(code);
+
+    if let Some((start, style)) = output_config.level_styles.range(..=code).next_back() {
+        if band.as_ref().map_or(true, |band| *start >= band.as_u16()) {
+            return style.paint(&level_text).to_string();
+        }
+    }
+
+    match band {
+        Some(LogLevel::DEBUG) => level_text.yellow().to_string(),
+        Some(LogLevel::INFO) => level_text.cyan().to_string(),
+        Some(LogLevel::WARN) => level_text.magenta().to_string(),
+        Some(LogLevel::ERROR) => level_text.red().to_string(),
+        Some(LogLevel::FATAL) => level_text.reverse().to_string(),
+        _ => level_text,
+    }
+}
+
+/// Returns the text naming the log level.
+/// The numeric value of the level is displayed instead of its name if `numeric_level` is set.
+/// Custom levels are named by their configured label, falling back to `LVLnnn`.
+///
+/// # Arguments
+///
+/// * `level` - log level to display
+/// * `output_config` - Configuration defining how the level is displayed
+///
+fn log_level_text(level: &LogLevel, output_config: &LoggerOutputConfig) -> String {
+    if output_config.numeric_level {
+        return level.as_u16().to_string();
+    }
+
+    match level {
+        LogLevel::OTHER(code) => match custom_level_label(*code, output_config) {
+            Some(label) => label.to_string(),
+            None => level.as_string().into_owned(),
+        },
+        _ => level.as_string().into_owned(),
+    }
+}
+
+/// Returns the text displaying the log level, right aligned in a gutter of `level_width`
+/// characters.
+///
+/// # Arguments
+///
+/// * `level` - log level to display
+/// * `output_config` - Configuration defining how the level is displayed
+///
+fn format_log_level(level: &LogLevel, output_config: &LoggerOutputConfig) -> String {
+    let level_text = log_level_text(level, output_config);
+
+    format!("{:>width$}", level_text, width = output_config.level_width)
+}
+
+/// Returns the passed rendered log level preceded by the icon configured for the level in
+/// `level_icons`, if any.
+///
+/// # Arguments
+///
+/// * `level` - log level being displayed
+/// * `level_text` - rendered log level
+/// * `output_config` - Configuration defining the level icons
+///
+fn prefix_level_icon(
+    level: &LogLevel,
+    level_text: String,
+    output_config: &LoggerOutputConfig,
+) -> String {
+    match output_config
+        .level_icons
+        .as_ref()
+        .and_then(|icons| icons.get(level))
+    {
+        Some(icon) => format!("{} {}", icon, level_text),
+        None => level_text,
+    }
+}
+
+/// Returns the color of the first highlight rule whose keyword is contained in the message.
+///
+/// # Arguments
+///
+/// * `msg` - log message to match against the rules
+/// * `output_config` - Configuration defining the highlight rules
+///
+fn highlight_color(msg: &str, output_config: &LoggerOutputConfig) -> Option<Color> {
+    output_config
+        .highlight_rules
+        .iter()
+        .find(|(keyword, _)| !keyword.is_empty() && msg.contains(keyword.as_str()))
+        .map(|(_, color)| *color)
+}
+
+/// Returns the text displaying a time relative to the first line of the stream (or the
+/// configured anchor time) as signed seconds with millisecond precision (eg `+1.234`). Lines
+/// logged before it have a negative offset.
+///
+/// # Arguments
+///
+/// * `offset` - difference between the time of the line and the time it is relative to
+///
+fn format_time_offset(offset: TimeDelta) -> String {
+    let millis = offset.num_milliseconds();
+    let sign = if millis < 0 { '-' } else { '+' };
+    let millis = millis.unsigned_abs();
+
+    format!("{}{}.{:03}", sign, millis / 1000, millis % 1000)
+}
+
+/// Returns the palette color of the passed component if `color_by_component` is set. The
+/// color is picked by hashing the component with FNV-1a so that a component gets the same
+/// color on every line and in every run.
+///
+/// # Arguments
+///
+/// * `component` - component of the line, or its name when it has no component
+/// * `output_config` - Configuration defining whether components are colored
+///
+fn component_color(component: &str, output_config: &LoggerOutputConfig) -> Option<Color> {
+    if !output_config.color_by_component {
+        return None;
+    }
+
+    let hash = component.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+
+    Some(COMPONENT_PALETTE[hash as usize % COMPONENT_PALETTE.len()])
+}
+
+/// Returns the passed rendered time padded on the left or truncated to `width` characters if
+/// `fixed_time_width` is set, so that the columns following it line up. Otherwise, the time
+/// is returned unchanged.
+///
+/// # Arguments
+///
+/// * `time` - rendered time of the line
+/// * `width` - number of characters the time is displayed in
+/// * `output_config` - Configuration defining whether the time has a fixed width
+///
+fn fit_time_width(time: String, width: usize, output_config: &LoggerOutputConfig) -> String {
+    if !output_config.fixed_time_width {
+        return time;
+    }
+
+    let time: String = time.chars().take(width).collect();
+    format!("{:>width$}", time, width = width)
+}
+
+/// Returns the time column of the long and short formats: the time of the line relative to
+/// the time origin, as epoch milliseconds or rendered by `render_time`, replaced by a
+/// placeholder when the line has no time.
+///
+/// # Arguments
+///
+/// * `line` - log line whose time is displayed
+/// * `render_time` - function rendering an absolute time in the local or UTC timezone
+/// * `output_config` - Configuration defining how times are displayed
+///
+fn format_time_column<F>(
+    line: &BunyanLine,
+    render_time: F,
+    output_config: &LoggerOutputConfig,
+) -> String
+where
+    F: Fn(DateTime<Utc>) -> String,
+{
+    // Absolute times are as wide as the rendering of any other time, eg the epoch
+    let time = if let Some(origin) = line.time_origin {
+        let offset = format_time_offset(line.time - origin);
+        fit_time_width(offset, RELATIVE_TIME_WIDTH, output_config)
+    } else if output_config.time_as_epoch {
+        let millis = line.time.timestamp_millis().to_string();
+        fit_time_width(millis, EPOCH_TIME_WIDTH, output_config)
+    } else {
+        let width = render_time(DateTime::UNIX_EPOCH).chars().count();
+        fit_time_width(render_time(line.time), width, output_config)
+    };
+
+    if line.is_time_unknown {
+        fit_time_width(
+            UNKNOWN_TIME.to_string(),
+            time.chars().count(),
+            output_config,
+        )
+    } else {
+        time
+    }
+}
+
+/// Returns the number of characters of the passed text that are displayed, leaving out the
+/// escape sequences of colors and hyperlinks.
+///
+/// # Arguments
+///
+/// * `text` - text to measure
+///
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            width += 1;
+            continue;
+        }
+
+        match chars.next() {
+            // Control sequences (eg colors) end with a character in the `@` to `~` range
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // Operating system commands (eg hyperlinks) end with a string terminator
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    width
+}
+
+/// Writes the header of the long format, made of the time, level, name and pid followed by
+/// the location (hostname and src) of the line. If `wrap_header` is set and the header is
+/// wider than the terminal, the location is written on a continuation line instead.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `header` - rendered time, level, name and pid of the line
+/// * `location` - rendered hostname and src of the line
+/// * `output_config` - Configuration defining whether the header is wrapped
+///
+fn write_header<W: Write>(
+    writer: &mut W,
+    header: &[u8],
+    location: &[u8],
+    output_config: &LoggerOutputConfig,
+) {
+    let header = String::from_utf8_lossy(header);
+    let location = String::from_utf8_lossy(location);
+
+    let is_too_wide = |width: usize| visible_width(&header) + visible_width(&location) > width;
+
+    if output_config.wrap_header && output_config.terminal_width.is_some_and(is_too_wide) {
+        wln!(writer, "{}", header);
+        w!(
+            writer,
+            "{:indent$}{}",
+            "",
+            location.trim_start(),
+            indent = BASE_INDENT_SIZE
+        );
+    } else {
+        w!(writer, "{}{}", header, location);
+    }
+}
+
 /// Validates that the passed `BunyanLine` is of the correct structure where it can be parsed
 /// without problems.
 ///
 /// # Arguments
 ///
 /// * `line` - log line to validate
+/// * `output_config` - Configuration defining the field holding the request id
 ///
 /// # Errors
 ///
 /// This function will return None if no errors have been encountered. In the case of parsing logic
 /// errors where the JSON data is not in the expected format, it will return a
 /// `Option<BunyanLogParseError>`.
-fn validate_log_data_structure(line: &BunyanLine) -> Option<BunyanLogParseError> {
+fn validate_log_data_structure(
+    line: &BunyanLine,
+    output_config: &LoggerOutputConfig,
+) -> Option<BunyanLogParseError> {
     fn find_headers(map: &Map<String, Value>) -> Option<&Value> {
         if let Some(headers) = map.get("headers") {
             if headers.is_string() || headers.is_object() {
@@ -885,11 +1931,13 @@ fn validate_log_data_structure(line: &BunyanLine) -> Option<BunyanLogParseError>
     }
 
     // Validate req_id
-    if let Some(req_id) = line.other.get("req_id") {
+    let req_id_field = output_config.req_id_field();
+    if let Some(req_id) = line.other.get(req_id_field) {
         if !(req_id.is_string() || req_id.is_number() || req_id.is_null()) {
-            return Some(BunyanLogParseError::new(
-                "[req_id] must be a string or number",
-            ));
+            return Some(BunyanLogParseError::new(format!(
+                "[{}] must be a string or number",
+                req_id_field
+            )));
         }
     }
 
@@ -989,112 +2037,148 @@ impl Logger for BunyanLine {
     fn write_long_format<W: Write>(
         &self,
         writer: &mut W,
-        _output_config: &LoggerOutputConfig,
+        output_config: &LoggerOutputConfig,
     ) -> ParseResult {
-        fn colorize_log_level(level: LogLevel) -> String {
-            match level {
-                LogLevel::TRACE => level.to_string(),
-                LogLevel::DEBUG => level.to_string().yellow().to_string(),
-                LogLevel::INFO => level.to_string().cyan().to_string(),
-                LogLevel::WARN => level.to_string().magenta().to_string(),
-                LogLevel::ERROR => level.to_string().red().to_string(),
-                LogLevel::FATAL => level.to_string().reverse().to_string(),
-                LogLevel::OTHER(_code) => level.to_string(),
-            }
-        }
-
-        if let Some(err) = validate_log_data_structure(self) {
+        if let Some(err) = validate_log_data_structure(self, output_config) {
             return Err(err);
         }
 
         let log_level: LogLevel = self.level.into();
+        let field_styles = &output_config.field_styles;
 
         // Write the [time]
-        let time = if _output_config.display_local_time {
-            self.time
-                .with_timezone(&Local)
-                .to_rfc3339_opts(SecondsFormat::Millis, true)
+        let render_time = |time: DateTime<Utc>| {
+            if output_config.display_local_time {
+                time.with_timezone(&Local)
+                    .to_rfc3339_opts(SecondsFormat::Millis, true)
+            } else {
+                time.to_rfc3339_opts(SecondsFormat::Millis, true)
+            }
+        };
+
+        let time = format_time_column(self, render_time, output_config);
+
+        // The header is written once it is complete because it may be wrapped
+        let mut header: Vec<u8> = Vec::new();
+
+        if output_config.time_brackets {
+            w!(
+                header,
+                "{}{}{}",
+                "[".blue(),
+                time.bright_white(),
+                "]".blue()
+            );
         } else {
-            self.time.to_rfc3339_opts(SecondsFormat::Millis, true)
+            w!(header, "{}", time.bright_white());
+        }
+
+        let separator = output_config.component_separator;
+        let level_text = format_log_level(&log_level, output_config);
+        let name = self.display_name(output_config);
+        let header_color =
+            component_color(self.component.as_deref().unwrap_or(name), output_config);
+        let paint_header = |text: &str| match header_color {
+            Some(color) => text.color(color).to_string(),
+            None => text.to_string(),
         };
 
+        // write the log [level] and app [name]
+        let level_text = colorize_log_level(&log_level, level_text, output_config);
         w!(
-            writer,
-            "{}{}{}",
-            "[".blue(),
-            time.bright_white(),
-            "]".blue()
+            header,
+            " {}: {}{}",
+            prefix_level_icon(&log_level, level_text, output_config),
+            paint_header(name),
+            separator
         );
 
-        // write the log [level] and app [name]
-        w!(writer, " {}: {}/", colorize_log_level(log_level), self.name);
-
         // If present, write the [component]
         if let Some(ref component) = self.component {
-            w!(writer, "{}/", component);
+            w!(header, "{}{}", paint_header(component), separator);
         }
 
-        // Write the [pid] and [hostname]
-        w!(writer, "{} on {}", self.pid, self.hostname);
+        // Write the [pid]
+        w!(header, "{}", self.pid);
+
+        // Write the [hostname]
+        let mut location: Vec<u8> = Vec::new();
+        w!(location, " on {}", self.hostname);
 
         let other = &mut self.other.clone();
+        hoist_context_fields(other, output_config);
 
         // If present, write the source line reference [src]
-        write_src(writer, other);
+        write_src(&mut location, other, output_config);
+
+        write_header(writer, &header, &location, output_config);
+
+        // If requested, write the schema version [v]
+        write_version(writer, self.v, output_config);
 
         let mut details: Vec<String> = Vec::new();
+        let msg = collapse_msg_whitespace(&self.msg, output_config);
+        let msg = escape_control_chars(&msg, output_config);
 
         // If our log message [msg] contains a line break, we display it in the details section
-        if self.msg.contains('\n') {
-            let indented_msg = format!("{:indent$}{}", "", self.msg, indent = BASE_INDENT_SIZE);
+        if msg.contains('\n') {
+            let indented_msg = format!("{:indent$}{}", "", msg, indent = BASE_INDENT_SIZE);
             details.push(indented_msg)
         // Write the log message [msg] as is because there is no line break
-        } else if !self.msg.is_empty() {
-            w!(writer, ": {}", self.msg.cyan());
+        } else if let Some(color) = highlight_color(&msg, output_config) {
+            w!(writer, ": {}", msg.color(color));
+        } else if !msg.is_empty() {
+            match field_styles.get("msg") {
+                Some(style) => w!(writer, ": {}", style.paint(&msg)),
+                None => w!(writer, ": {}", msg.cyan()),
+            }
         } else {
             w!(writer, ":");
         }
 
-        write_all_extra_params(writer, other, &mut details);
+        write_all_extra_params(writer, other, &mut details, output_config);
 
         // Write line feed finishing the first line
         wln!(writer);
 
         let wrapped_writer = &mut DividerWriter::new(writer, true);
 
-        // If present, write the request [req]
-        write_req(wrapped_writer, "req", other);
+        // The compact layout only keeps the parameters summarized on the first line
+        if !output_config.compact {
+            // If present, write the request [req]
+            write_req(wrapped_writer, "req", other, output_config);
 
-        if wrapped_writer.has_been_written {
-            wrapped_writer.mark_divider_as_unwritten();
-        }
+            if wrapped_writer.has_been_written {
+                wrapped_writer.mark_divider_as_unwritten();
+            }
 
-        // If present, write the client request [client_req]
-        write_req(wrapped_writer, "client_req", other);
+            // If present, write the client request [client_req]
+            write_req(wrapped_writer, "client_req", other, output_config);
 
-        if wrapped_writer.has_been_written {
-            wrapped_writer.mark_divider_as_unwritten();
-        }
+            if wrapped_writer.has_been_written {
+                wrapped_writer.mark_divider_as_unwritten();
+            }
 
-        // If present, write the response [res]
-        write_res(wrapped_writer, "res", other);
+            // If present, write the response [res]
+            write_res(wrapped_writer, "res", other, output_config);
 
-        if wrapped_writer.has_been_written {
-            wrapped_writer.mark_divider_as_unwritten();
-        }
+            if wrapped_writer.has_been_written {
+                wrapped_writer.mark_divider_as_unwritten();
+            }
 
-        // If present, write the response [client_res]
-        write_res(wrapped_writer, "client_res", other);
+            // If present, write the response [client_res]
+            write_res(wrapped_writer, "client_res", other, output_config);
 
-        if wrapped_writer.has_been_written {
-            wrapped_writer.mark_divider_as_unwritten();
-        }
+            if wrapped_writer.has_been_written {
+                wrapped_writer.mark_divider_as_unwritten();
+            }
 
-        // If present, write the error information [err]
-        write_err(wrapped_writer, other);
+            // If present, write the error information [err]
+            write_err(wrapped_writer, other, output_config);
 
-        if wrapped_writer.has_been_written {
-            wrapped_writer.mark_divider_as_unwritten();
+            if wrapped_writer.has_been_written {
+                wrapped_writer.mark_divider_as_unwritten();
+            }
         }
 
         // Write out all of the values stored in the details vector
@@ -1106,59 +2190,72 @@ impl Logger for BunyanLine {
     fn write_short_format<W: Write>(
         &self,
         writer: &mut W,
-        _output_config: &LoggerOutputConfig,
+        output_config: &LoggerOutputConfig,
     ) -> ParseResult {
-        pub fn right_align_and_colorize_log_level(level: LogLevel) -> String {
-            match level {
-                LogLevel::TRACE => format!("{: >5}", level),
-                LogLevel::DEBUG => format!("{: >5}", level).yellow().to_string(),
-                LogLevel::INFO => format!("{: >5}", level).cyan().to_string(),
-                LogLevel::WARN => format!("{: >5}", level).magenta().to_string(),
-                LogLevel::ERROR => format!("{: >5}", level).red().to_string(),
-                LogLevel::FATAL => format!("{: >5}", level).reverse().to_string(),
-                LogLevel::OTHER(_code) => level.to_string(),
-            }
-        }
-
-        if let Some(err) = validate_log_data_structure(self) {
+        if let Some(err) = validate_log_data_structure(self, output_config) {
             return Err(err);
         }
 
         let log_level: LogLevel = self.level.into();
+        let field_styles = &output_config.field_styles;
 
         // Write the [time]
-        let time = if _output_config.display_local_time {
-            self.time.with_timezone(&Local).format("%H:%M:%S%.3f")
-        } else {
-            self.time.format("%H:%M:%S%.3fZ")
-        }
-        .to_string();
+        let render_time = |time: DateTime<Utc>| {
+            if output_config.display_local_time {
+                time.with_timezone(&Local)
+                    .format("%H:%M:%S%.3f")
+                    .to_string()
+            } else {
+                time.format("%H:%M:%S%.3fZ").to_string()
+            }
+        };
+
+        let time = format_time_column(self, render_time, output_config);
 
         w!(writer, "{}", time.bright_white());
 
         // write the log [level] and app [name]
-        let level_right_indented = right_align_and_colorize_log_level(log_level);
-        w!(writer, " {} {}", level_right_indented, self.name);
+        let level_text = format_log_level(&log_level, output_config);
+        let level_right_indented = colorize_log_level(&log_level, level_text, output_config);
+        let level_right_indented =
+            prefix_level_icon(&log_level, level_right_indented, output_config);
+        let name = self.display_name(output_config);
+        let name = match component_color(self.component.as_deref().unwrap_or(name), output_config) {
+            Some(color) => name.color(color).to_string(),
+            None => name.to_string(),
+        };
+        w!(writer, " {} {}", level_right_indented, name);
 
         let other = &mut self.other.clone();
+        hoist_context_fields(other, output_config);
 
         // If present, write the source line reference [src]
-        write_src(writer, other);
+        write_src(writer, other, output_config);
+
+        // If requested, write the schema version [v]
+        write_version(writer, self.v, output_config);
 
         let mut details: Vec<String> = Vec::new();
+        let msg = collapse_msg_whitespace(&self.msg, output_config);
+        let msg = escape_control_chars(&msg, output_config);
 
         // If our log message [msg] contains a line break, we display it in the details section
-        if self.msg.contains('\n') {
-            let indented_msg = format!("{:indent$}{}", "", self.msg, indent = BASE_INDENT_SIZE);
+        if msg.contains('\n') {
+            let indented_msg = format!("{:indent$}{}", "", msg, indent = BASE_INDENT_SIZE);
             details.push(indented_msg)
         // Write the log message [msg] as is because there is no line break
-        } else if !self.msg.is_empty() {
-            w!(writer, ": {}", self.msg.cyan());
+        } else if let Some(color) = highlight_color(&msg, output_config) {
+            w!(writer, ": {}", msg.color(color));
+        } else if !msg.is_empty() {
+            match field_styles.get("msg") {
+                Some(style) => w!(writer, ": {}", style.paint(&msg)),
+                None => w!(writer, ": {}", msg.cyan()),
+            }
         } else {
             w!(writer, ":");
         }
 
-        write_all_extra_params(writer, other, &mut details);
+        write_all_extra_params(writer, other, &mut details, output_config);
 
         // Write line feed finishing the first line
         wln!(writer);
@@ -1166,35 +2263,35 @@ impl Logger for BunyanLine {
         let wrapped_writer = &mut DividerWriter::new(writer, true);
 
         // If present, write the request [req]
-        write_req(wrapped_writer, "req", other);
+        write_req(wrapped_writer, "req", other, output_config);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the client request [client_req]
-        write_req(wrapped_writer, "client_req", other);
+        write_req(wrapped_writer, "client_req", other, output_config);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the response [res]
-        write_res(wrapped_writer, "res", other);
+        write_res(wrapped_writer, "res", other, output_config);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the response [client_res]
-        write_res(wrapped_writer, "client_res", other);
+        write_res(wrapped_writer, "client_res", other, output_config);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
         }
 
         // If present, write the error information [err]
-        write_err(wrapped_writer, other);
+        write_err(wrapped_writer, other, output_config);
 
         if wrapped_writer.has_been_written {
             wrapped_writer.mark_divider_as_unwritten();
@@ -1209,16 +2306,20 @@ impl Logger for BunyanLine {
     fn write_simple_format<W: Write>(
         &self,
         writer: &mut W,
-        _output_config: &LoggerOutputConfig,
+        output_config: &LoggerOutputConfig,
     ) -> ParseResult {
-        if let Some(err) = validate_log_data_structure(self) {
+        if let Some(err) = validate_log_data_structure(self, output_config) {
             return Err(err);
         }
 
         let log_level: LogLevel = self.level.into();
 
         // write the log [level]
-        wln!(writer, "{} - {}", log_level.as_string(), self.msg);
+        let level_text = log_level_text(&log_level, output_config);
+        let level_text = prefix_level_icon(&log_level, level_text, output_config);
+        let msg = collapse_msg_whitespace(&self.msg, output_config);
+        let msg = escape_control_chars(&msg, output_config);
+        wln!(writer, "{} - {}", level_text, msg);
 
         Ok(())
     }
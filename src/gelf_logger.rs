@@ -0,0 +1,96 @@
+use std::io::Write;
+
+use serde_json::map::Map;
+use serde_json::{Number, Value};
+
+use crate::{BunyanLine, LogLevel, LoggerOutputConfig};
+
+/// Version of the GELF specification that is output
+const GELF_VERSION: &str = "1.1";
+
+/// Converts a bunyan log level to the syslog severity used by GELF.
+///
+/// # Arguments
+///
+/// * `level` - bunyan log level
+///
+fn syslog_severity(level: &LogLevel) -> u8 {
+    match level.as_u16() {
+        0..=29 => 7,  // TRACE, DEBUG => Debug
+        30..=39 => 6, // INFO => Informational
+        40..=49 => 4, // WARN => Warning
+        50..=59 => 3, // ERROR => Error
+        _ => 2,       // FATAL => Critical
+    }
+}
+
+/// Converts a JSON value to a GELF additional field value. GELF only allows strings and
+/// numbers, so all other types are converted to strings. Null values are omitted.
+///
+/// # Arguments
+///
+/// * `value` - JSON value of the additional field
+///
+fn additional_field_value(value: Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::String(_) | Value::Number(_) => Some(value),
+        Value::Bool(_) | Value::Array(_) | Value::Object(_) => {
+            Some(Value::String(value.to_string()))
+        }
+    }
+}
+
+/// Writes a log line as a single line of GELF 1.1 JSON suitable for ingestion by Graylog.
+/// The bunyan fields that don't map to GELF fields are written as additional fields
+/// prefixed with `_`.
+///
+/// # Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `log` - log line to write
+/// * `output_config` - Configuration defining the name of lines without a name
+///
+pub fn write_gelf_line<W>(writer: &mut W, log: BunyanLine, output_config: &LoggerOutputConfig)
+where
+    W: Write,
+{
+    let log_level: LogLevel = log.level.into();
+    let name = log.display_name(output_config).to_string();
+
+    let mut gelf: Map<String, Value> = Map::new();
+    gelf.insert("version".into(), GELF_VERSION.into());
+    gelf.insert("host".into(), log.hostname.into());
+    gelf.insert("short_message".into(), log.msg.into());
+    // Without a time of its own, the line is stamped by Graylog when it is received
+    if !log.has_fallback_time {
+        let timestamp = log.time.timestamp_millis() as f64 / 1000.0;
+        gelf.insert(
+            "timestamp".into(),
+            Number::from_f64(timestamp).map_or(Value::Null, Value::Number),
+        );
+    }
+    gelf.insert("level".into(), syslog_severity(&log_level).into());
+    gelf.insert("_name".into(), name.into());
+    gelf.insert("_pid".into(), log.pid.into());
+
+    if let Some(component) = log.component {
+        gelf.insert("_component".into(), component.into());
+    }
+
+    for (k, v) in log.other {
+        // [_id] is reserved by GELF
+        if k == "id" {
+            continue;
+        }
+
+        if let Some(value) = additional_field_value(v) {
+            gelf.insert(format!("_{}", k), value);
+        }
+    }
+
+    match serde_json::to_string(&gelf) {
+        Ok(json) => wln!(writer, "{}", json),
+        Err(_) => panic!("Unable to write json string"),
+    }
+}
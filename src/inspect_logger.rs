@@ -6,6 +6,8 @@ use serde_json::Value;
 
 use colored::*;
 
+use crate::LoggerOutputConfig;
+
 /// Default indent size in spaces
 const BASE_INDENT_SIZE: usize = 2;
 
@@ -15,11 +17,19 @@ const BASE_INDENT_SIZE: usize = 2;
 ///
 /// * `writer` - Write implementation to output data to
 /// * `map` - Mutable map containing JSON data.
-///
-pub fn write_inspect_line<W: Write>(writer: &mut W, map: Map<String, Value>)
-where
-    W: Write,
-{
+/// * `output_config` - Configuration defining whether the stable layout is used
+///
+pub fn write_inspect_line<W: Write>(
+    writer: &mut W,
+    map: Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) {
+    if output_config.stable_inspect {
+        write_stable_value(writer, Value::Object(map), 0);
+        wln!(writer);
+        return;
+    }
+
     w!(writer, "{}{{\n", "".bright_white());
     let leading_spaces = " ".repeat(BASE_INDENT_SIZE);
     let mut itr = map.into_iter().peekable();
@@ -47,10 +57,7 @@ where
 /// * `value` - Entry to write
 /// * `indent` - number of spaces to indent
 ///
-fn write_value<W: Write>(writer: &mut W, value: Value, indent: usize)
-where
-    W: Write,
-{
+fn write_value<W: Write>(writer: &mut W, value: Value, indent: usize) {
     match value {
         Value::String(string) => {
             let line = if string.len() > 80 {
@@ -68,6 +75,63 @@ where
     }
 }
 
+/// Writes a JSON value with keys sorted and every array element and object entry on its own
+/// line, indented by two spaces per level. Unlike `write_value`, the layout doesn't depend on
+/// the length of values or on the order of the keys in the input.
+///
+/// Arguments
+///
+/// * `writer` - Write implementation to output data to
+/// * `value` - Entry to write
+/// * `indent` - number of spaces the line of the value is indented by
+///
+fn write_stable_value<W: Write>(writer: &mut W, value: Value, indent: usize) {
+    let inner_indent = " ".repeat(indent + BASE_INDENT_SIZE);
+
+    match value {
+        Value::String(string) => {
+            let line = escape(string).replace('\n', "\\n");
+            w!(writer, "{}{}{}", "'".green(), line.green(), "'".green());
+        }
+        Value::Array(array) if !array.is_empty() => {
+            w!(writer, "{}\n", "[".bright_white());
+
+            let mut itr = array.into_iter().peekable();
+            while let Some(next) = itr.next() {
+                w!(writer, "{}", inner_indent);
+                write_stable_value(writer, next, indent + BASE_INDENT_SIZE);
+
+                if itr.peek().is_some() {
+                    w!(writer, "{}", ",".bright_white());
+                }
+                w!(writer, "\n");
+            }
+
+            w!(writer, "{}{}", " ".repeat(indent), "]".bright_white());
+        }
+        Value::Object(obj) if !obj.is_empty() => {
+            w!(writer, "{}\n", "{".bright_white());
+
+            let mut entries: Vec<(String, Value)> = obj.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            let mut itr = entries.into_iter().peekable();
+            while let Some((k, v)) = itr.next() {
+                w!(writer, "{}{}: ", inner_indent, k.bright_white());
+                write_stable_value(writer, v, indent + BASE_INDENT_SIZE);
+
+                if itr.peek().is_some() {
+                    w!(writer, "{}", ",".bright_white());
+                }
+                w!(writer, "\n");
+            }
+
+            w!(writer, "{}{}", " ".repeat(indent), "}".bright_white());
+        }
+        _ => write_value(writer, value, indent),
+    }
+}
+
 /// Writes a JSON array.
 ///
 /// Arguments
@@ -76,10 +140,7 @@ where
 /// * `array` - array to write
 /// * `indent` - number of spaces to indent
 ///
-fn write_array<W: Write>(writer: &mut W, array: Vec<Value>, indent: usize)
-where
-    W: Write,
-{
+fn write_array<W: Write>(writer: &mut W, array: Vec<Value>, indent: usize) {
     let mut itr = array.into_iter().peekable();
 
     w!(writer, "{}", "[".bright_white());
@@ -109,10 +170,7 @@ where
 /// * `obj` - object to write
 /// * `indent` - number of spaces to indent
 ///
-fn write_object<W: Write>(writer: &mut W, obj: Map<String, Value>, indent: usize)
-where
-    W: Write,
-{
+fn write_object<W: Write>(writer: &mut W, obj: Map<String, Value>, indent: usize) {
     w!(writer, "{}", "{".bright_white());
 
     let mut itr = obj.into_iter().peekable();
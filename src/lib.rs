@@ -6,27 +6,58 @@ extern crate httpstatus;
 extern crate json_pretty;
 extern crate serde;
 extern crate serde_json;
+extern crate toml;
 
 #[macro_use]
 mod macros;
+#[cfg(feature = "async")]
+mod async_output;
 mod condition_filter;
+mod custom_renderers;
 mod date_deserializer;
 mod divider_writer;
+mod duplicate_keys;
 mod errors;
+mod field_styles;
+mod follow_reader;
 mod formatting_logger;
+mod gelf_logger;
 mod inspect_logger;
+mod line_ending_writer;
+mod line_prefix_writer;
+mod trailing_newline_writer;
 
+use crate::divider_writer::DIVIDER;
 use crate::errors::LogLevelParseError;
+use crate::gelf_logger::write_gelf_line;
 use crate::inspect_logger::write_inspect_line;
 
+#[cfg(feature = "async")]
+pub use crate::async_output::write_bunyan_output_async;
 pub use crate::condition_filter::ConditionFilter;
+pub use crate::custom_renderers::{CustomRenderer, CustomRenderers};
+pub use crate::duplicate_keys::DuplicateKeys;
+pub use crate::errors::{BunyanLogParseError, ViewError};
+pub use crate::field_styles::{FieldStyle, FieldStyles};
+pub use crate::follow_reader::FollowReader;
+pub use crate::line_ending_writer::LineEnding;
+
+use crate::line_ending_writer::LineEndingWriter;
+use crate::line_prefix_writer::LinePrefixWriter;
+use crate::trailing_newline_writer::TrailingNewlineWriter;
 
 use std::borrow::Cow;
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 use std::fmt;
-use std::io::{BufRead, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::ops::Range;
 
 use crate::errors::{Error, Kind, ParseResult};
 use chrono::prelude::*;
+use colored::*;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::map::Map;
 use serde_json::Error as SerdeError;
 use serde_json::Value;
@@ -35,6 +66,8 @@ use json_pretty::PrettyFormatter;
 
 /// Default indent size in spaces
 const BASE_INDENT_SIZE: usize = 4;
+/// Default indent size in spaces of nested objects in the long and short formats
+const OBJECT_INDENT_STEP: usize = 2;
 /// Minimum fields needed to be a valid bunyan log line
 const REQUIRED_FIELDS: [&str; 6] = ["v", "level", "hostname", "pid", "time", "msg"];
 
@@ -75,6 +108,21 @@ impl LogLevel {
         }
     }
 
+    /// Returns the default icons displayed before the levels when `level_icons` is set.
+    pub fn default_icons() -> HashMap<LogLevel, String> {
+        [
+            (LogLevel::TRACE, "🔍"),
+            (LogLevel::DEBUG, "🐛"),
+            (LogLevel::INFO, "🔵"),
+            (LogLevel::WARN, "🟡"),
+            (LogLevel::ERROR, "🔴"),
+            (LogLevel::FATAL, "💀"),
+        ]
+        .into_iter()
+        .map(|(level, icon)| (level, icon.to_string()))
+        .collect()
+    }
+
     pub fn parse<S: Into<String>>(level: S) -> Result<LogLevel, LogLevelParseError> {
         let level = level.into().to_ascii_uppercase();
 
@@ -128,22 +176,222 @@ impl fmt::Display for LogLevel {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Default width of the gutter the log level is right aligned in, fitting the longest level name
+const LEVEL_WIDTH: usize = 5;
+
+/// Default maximum characters for a string value displayed inline in the extra parameters
+const INLINE_VALUE_THRESHOLD: usize = 50;
+
+/// Name displayed for lines without a [name] when no default name has been configured
+const UNNAMED_LOGGER: &str = "unnamed";
+
+/// Field holding the id of the request a line was logged for, as in node-bunyan
+const DEFAULT_REQ_ID_FIELD: &str = "req_id";
+
+/// Keys checked in order for the timestamp of a log line when `time` is not present
+const TIME_ALIASES: [&str; 2] = ["timestamp", "@timestamp"];
+
+/// Keys checked in order for the message of a log line when `msg` is not present
+const MSG_ALIASES: [&str; 2] = ["message", "log"];
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "RawBunyanLine")]
 #[allow(dead_code)]
 pub struct BunyanLine {
-    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
     hostname: String,
     pid: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
     component: Option<String>,
     level: u16,
     msg: String,
     #[serde(with = "date_deserializer")]
     time: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    v: Option<u8>,
+    #[serde(flatten)]
+    other: Map<String, Value>,
+    /// Time that [time] is displayed relative to when `relative_time` is enabled
+    #[serde(skip)]
+    time_origin: Option<DateTime<Utc>>,
+    /// Whether the line had no time, which is then displayed as unknown
+    #[serde(skip)]
+    is_time_unknown: bool,
+    /// Whether [time] was made up according to `time_fallback` because the line had none, in
+    /// which case it isn't part of the JSON of the line
+    #[serde(skip)]
+    has_fallback_time: bool,
+}
+
+/// Log line as deserialized before fields that may be present under alternate keys have
+/// been resolved.
+#[derive(Deserialize)]
+struct RawBunyanLine {
+    name: Option<String>,
+    hostname: String,
+    pid: usize,
+    component: Option<String>,
+    level: u16,
+    #[serde(default, deserialize_with = "deserialize_msg")]
+    msg: Option<String>,
+    #[serde(default, deserialize_with = "date_deserializer::deserialize_option")]
+    time: Option<DateTime<Utc>>,
     v: Option<u8>,
     #[serde(flatten)]
     other: Map<String, Value>,
 }
 
+/// Returns the message held by the passed value. Messages that aren't strings (eg numbers or
+/// objects) are displayed as JSON rather than rejecting the line, and null messages are
+/// missing.
+///
+/// # Arguments
+/// * `value` - value of the [msg] field or of one of its aliases
+///
+fn msg_from_value(value: Value) -> Option<String> {
+    match value {
+        Value::String(msg) => Some(msg),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// Deserializes the [msg] of a log line, which may be any JSON value. See `msg_from_value`.
+fn deserialize_msg<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Value::deserialize(deserializer).map(msg_from_value)
+}
+
+impl TryFrom<RawBunyanLine> for BunyanLine {
+    type Error = BunyanLogParseError;
+
+    fn try_from(raw: RawBunyanLine) -> Result<Self, Self::Error> {
+        let mut other = raw.other;
+
+        // Lines without [time] may have their timestamp under one of its aliases. The
+        // alias is only consumed when [time] is missing so that lines with both still
+        // display the alias as an extra parameter.
+        let time = match raw.time {
+            Some(time) => time,
+            None => match TIME_ALIASES.iter().find_map(|alias| other.remove(*alias)) {
+                Some(value) => date_deserializer::parse_json_timestamp(&value)
+                    .map_err(BunyanLogParseError::new)?,
+                None => return Err(BunyanLogParseError::new("missing field `time`")),
+            },
+        };
+
+        // Lines without [msg] may have their message under one of its aliases
+        let msg = match raw.msg {
+            Some(msg) => msg,
+            None => match MSG_ALIASES
+                .iter()
+                .find_map(|alias| other.remove(*alias))
+                .and_then(msg_from_value)
+            {
+                Some(msg) => msg,
+                None => return Err(BunyanLogParseError::new("missing field `msg`")),
+            },
+        };
+
+        Ok(BunyanLine {
+            name: raw.name,
+            hostname: raw.hostname,
+            pid: raw.pid,
+            component: raw.component,
+            level: raw.level,
+            msg,
+            time,
+            v: raw.v,
+            other,
+            time_origin: None,
+            is_time_unknown: false,
+            has_fallback_time: false,
+        })
+    }
+}
+
+impl BunyanLine {
+    /// Returns the line as a bunyan JSON object so that it can be written back out as
+    /// NDJSON. The level is numeric, the time is RFC3339 and the extra parameters are
+    /// included as top level fields. Lines without a time are written without one, rather
+    /// than with the time they were given by `time_fallback`.
+    pub fn to_json(&self) -> Value {
+        let mut json = serde_json::to_value(self).expect("Unable to convert log line to json");
+        if let (true, Value::Object(map)) = (self.has_fallback_time, &mut json) {
+            map.remove("time");
+        }
+        json
+    }
+
+    /// Writes the line in the configured output format. Unlike `LogWriter::write_log`, this
+    /// doesn't panic: the formats that aren't rendered from the parsed line (the JSON,
+    /// inspect and passthrough formats) return an error.
+    ///
+    /// # Arguments
+    /// * `writer` - Write implementation to output data to
+    /// * `output_config` - Configuration defining the output format
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format isn't rendered from the parsed line, or if the data of
+    /// the line isn't in the structure expected by the format.
+    ///
+    pub fn render<W: Write>(
+        &self,
+        writer: &mut W,
+        output_config: &LoggerOutputConfig,
+    ) -> Result<(), ViewError> {
+        let result = match output_config.format {
+            LogFormat::Long => self.write_long_format(writer, output_config),
+            LogFormat::Short => self.write_short_format(writer, output_config),
+            LogFormat::Simple => self.write_simple_format(writer, output_config),
+            LogFormat::Gelf => {
+                write_gelf_line(writer, self.clone(), output_config);
+                Ok(())
+            }
+            LogFormat::Json(_)
+            | LogFormat::JsonArray(_)
+            | LogFormat::Inspect
+            | LogFormat::Passthrough => Err(BunyanLogParseError::new(format!(
+                "The {} format isn't rendered from a parsed line",
+                output_config.format.as_string()
+            ))),
+        };
+
+        // The rendered line is the only line, so its errors are reported on line 1
+        result.map_err(|e| ViewError::from_render_error(e, 1))
+    }
+
+    /// Returns the name of the logger that wrote the line. Lines without a [name] use the
+    /// configured default name.
+    ///
+    /// # Arguments
+    /// * `output_config` - Configuration defining the default name
+    ///
+    fn display_name<'a>(&'a self, output_config: &'a LoggerOutputConfig) -> &'a str {
+        self.name
+            .as_deref()
+            .or(output_config.default_name.as_deref())
+            .unwrap_or(UNNAMED_LOGGER)
+    }
+
+    /// Returns true if the line contains the named field, either as one of the standard
+    /// bunyan fields or as an extra parameter.
+    ///
+    /// # Arguments
+    /// * `field` - name of the field to look for
+    ///
+    fn has_field(&self, field: &str) -> bool {
+        match field {
+            "name" => self.name.is_some(),
+            "component" => self.component.is_some(),
+            "v" => self.v.is_some(),
+            "hostname" | "pid" | "level" | "msg" | "time" => true,
+            _ => self.other.contains_key(field),
+        }
+    }
+}
+
 pub trait Logger {
     fn write_long_format<W: Write>(
         &self,
@@ -164,16 +412,28 @@ pub trait Logger {
     ) -> ParseResult;
 }
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Hash)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum LogFormat {
     Json(i32),
     Inspect,
     Long,
     Short,
     Simple,
+    /// Reprints valid bunyan lines exactly as they were read
+    Passthrough,
+    /// GELF 1.1 JSON for ingestion by Graylog
+    Gelf,
+    /// Single JSON array of all of the lines written once the input has been read, which holds
+    /// the whole input in memory
+    JsonArray(i32),
 }
 
 impl LogFormat {
+    /// Indent of the JSON formats that are indented by the `json_indent` of the configuration
+    /// rather than by an indent of their own, eg `Json(LogFormat::CONFIGURED_INDENT)`
+    pub const CONFIGURED_INDENT: i32 = -999;
+
     pub fn as_string(&self) -> Cow<'static, str> {
         match *self {
             LogFormat::Json(indent) => format!("json-{}", indent).into(),
@@ -181,10 +441,40 @@ impl LogFormat {
             LogFormat::Long => "long".into(),
             LogFormat::Short => "short".into(),
             LogFormat::Simple => "simple".into(),
+            LogFormat::Passthrough => "passthrough".into(),
+            LogFormat::Gelf => "gelf".into(),
+            LogFormat::JsonArray(indent) => format!("json-array-{}", indent).into(),
         }
     }
 }
 
+/// How the colorization of the output is decided
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    /// Colorize according to the environment (`NO_COLOR`, `CLICOLOR`, `CLICOLOR_FORCE` and
+    /// whether STDOUT is a terminal)
+    #[default]
+    Auto,
+    /// Always colorize the output
+    Always,
+    /// Never colorize the output
+    Never,
+}
+
+/// Policy deciding how lines without a time (or one of its aliases) are handled
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimeFallback {
+    /// Lines without a time aren't valid log lines, as in node-bunyan
+    #[default]
+    Reject,
+    /// Lines without a time are displayed with the current time
+    Now,
+    /// Lines without a time are displayed with `?` in place of their time
+    Unknown,
+}
+
 pub trait LogWriter {
     fn write_log<W: Write>(
         &self,
@@ -205,26 +495,427 @@ impl LogWriter for LogFormat {
             LogFormat::Long => log.write_long_format(writer, output_config),
             LogFormat::Short => log.write_short_format(writer, output_config),
             LogFormat::Simple => log.write_simple_format(writer, output_config),
+            LogFormat::Gelf => {
+                write_gelf_line(writer, log, output_config);
+                Ok(())
+            }
             _ => panic!("Invalid format"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Configuration of the output, which can be read from a TOML file with [config_from_toml].
+/// Fields missing from the file keep their default value. The condition filter, field styles,
+/// custom renderers, highlight rules, parameter styles, level styles and level icons can't be
+/// read from a file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
 pub struct LoggerOutputConfig {
     pub indent: usize,
     pub is_strict: bool,
     pub is_debug: bool,
     pub level: Option<u16>,
+    #[serde(skip)]
     pub condition_filter: Option<ConditionFilter>,
     pub display_local_time: bool,
     pub format: LogFormat,
+    /// Echo blank lines to the output even when `is_strict` is enabled
+    pub keep_blank_lines: bool,
+    /// Character separating the name, component and pid in the long format header
+    pub component_separator: char,
+    /// Colors and attributes applied to specific fields by the long and short formats
+    #[serde(skip)]
+    pub field_styles: FieldStyles,
+    /// Warn once per stream on STDERR when a line has a missing or unrecognized `v` value
+    pub warn_unknown_version: bool,
+    /// Level thresholds keyed by component name that take precedence over `level`
+    pub component_levels: HashMap<String, u16>,
+    /// Number of characters each level of a nested object is indented in the long and short formats
+    pub object_indent_step: usize,
+    /// Optional character drawn at the start of each level of nested object indentation
+    pub object_indent_guide: Option<char>,
+    /// Append the reason a line couldn't be parsed to passed through lines
+    pub annotate_errors: bool,
+    /// Lines longer than this number of bytes that pass the filters are written as `json-0`
+    /// rather than formatted
+    pub max_line_bytes: Option<usize>,
+    /// Functions rendering specific extra parameters in the long and short formats
+    #[serde(skip)]
+    pub custom_renderers: CustomRenderers,
+    /// Display the numeric value of log levels rather than their names
+    pub numeric_level: bool,
+    /// Write the key of multiline string parameters on its own line followed by the indented value
+    pub multiline_key_on_own_line: bool,
+    /// Write the extra parameters of the long and short formats sorted by key
+    pub sort_params: bool,
+    /// Name displayed for lines that don't have a `name` field
+    pub default_name: Option<String>,
+    /// Write the errors displayed by `is_debug` as JSON objects rather than text
+    pub debug_json: bool,
+    /// Stop reading the input once this number of log lines have been rendered
+    pub limit: Option<usize>,
+    /// Width of the gutter the log level is right aligned in by the long and short formats
+    pub level_width: usize,
+    /// Write the query parameters of request urls on their own lines below the request summary
+    pub expand_query: bool,
+    /// Buffer the input and write lines grouped by the value of this field (eg `req_id`). The
+    /// whole input is held in memory until it has been read.
+    pub group_by: Option<String>,
+    /// Fields every log line must contain, lines missing any of them are handled as errors
+    pub require_fields: Vec<String>,
+    /// Display times as offsets in seconds from the time of the first rendered line
+    pub relative_time: bool,
+    /// Keywords paired with the color of messages containing them, the first match wins
+    #[serde(skip)]
+    pub highlight_rules: Vec<(String, Color)>,
+    /// Write the file and line of [src] as an OSC 8 hyperlink to the file when colors are enabled
+    pub src_as_hyperlink: bool,
+    /// Levels of the lines that are not displayed, regardless of the level thresholds
+    pub exclude_levels: Vec<u16>,
+    /// Longer string parameters are written in the details section rather than inline
+    pub inline_value_threshold: usize,
+    /// Only write the lines that couldn't be parsed as bunyan log lines
+    pub errors_only: bool,
+    /// Reason phrases displayed for HTTP status codes instead of the standard ones
+    pub status_phrases: HashMap<u16, String>,
+    /// Labels of custom levels keyed by the lowest code of the range each label covers
+    pub level_labels: BTreeMap<u16, String>,
+    /// Styles of the levels keyed by the lowest code of the range each style covers, up to
+    /// the next standard level. Levels without a style are colored like the closest lower
+    /// standard level (eg 45 like WARN).
+    #[serde(skip)]
+    pub level_styles: BTreeMap<u16, FieldStyle>,
+    /// Number of lines buffered to write lines that are slightly out of order by time, 0 disables
+    pub merge_window: usize,
+    /// Number of spaces replacing each tab in string parameters, tabs are kept when `None`
+    pub tab_width: Option<usize>,
+    /// Write short arrays of scalars inline with the other parameters rather than below the line
+    pub inline_scalar_arrays: bool,
+    /// Omit the line feed ending the last line of output
+    pub no_trailing_newline: bool,
+    /// Skip the request, response and error blocks below the first line of the long format
+    pub compact: bool,
+    /// Write a divider line between consecutive lines having different `req_id` values
+    pub divider_on_reqid_change: bool,
+    /// Append the bunyan schema version `(v{n})` to the header of each line
+    pub show_version: bool,
+    /// Replace the control characters of rendered strings, other than line feeds and tabs,
+    /// by their `\xNN` escapes
+    pub escape_control_chars: bool,
+    /// Which value is kept when a line contains the same key more than once
+    pub duplicate_keys: DuplicateKeys,
+    /// Text written at the start of each line of output, eg the name of the file being read
+    pub line_prefix: Option<String>,
+    /// Pretty print request bodies that are strings containing a JSON object or array
+    pub pretty_json_bodies: bool,
+    /// Stop at the first line that can't be parsed or rendered and return its error
+    pub fail_fast: bool,
+    /// Pad or truncate the displayed time to a fixed width so that the following columns align
+    pub fixed_time_width: bool,
+    /// Color the name and component of each line with a color picked from its component, or
+    /// its name when it has no component
+    pub color_by_component: bool,
+    /// Write the status line of responses whose `duration` or `responseTime` exceeds this
+    /// number of milliseconds in bold red
+    pub slow_response_ms: Option<u64>,
+    /// Write the inspect format with sorted keys and every array element and object entry on
+    /// its own line instead of mirroring node's `util.inspect`
+    pub stable_inspect: bool,
+    /// Only write the lines logged by the process with this pid
+    pub pid_filter: Option<usize>,
+    /// Display the time of the long and short formats as milliseconds since the epoch, which
+    /// takes precedence over `display_local_time`
+    pub time_as_epoch: bool,
+    /// Write only the number of lines that pass the filters instead of the lines themselves
+    pub count_only: bool,
+    /// Characters ending each line of output
+    pub line_ending: LineEnding,
+    /// Truncate OpenTelemetry trace and span IDs (`trace_id`, `span_id` and their camel case
+    /// spellings) to their first 8 hex digits
+    pub truncate_trace_ids: bool,
+    /// Display only the extra fields whose value changed since the previous displayed line.
+    /// The time, level, message and the other fields of the header are always displayed.
+    pub diff_mode: bool,
+    /// Wrap the time at the start of the long format header in brackets
+    pub time_brackets: bool,
+    /// Name of an object field whose keys are displayed as extra parameters of the line in
+    /// the long and short formats instead of as a nested object
+    pub context_field: Option<String>,
+    /// How lines without a time are handled
+    pub time_fallback: TimeFallback,
+    /// Replace each run of whitespace in the message with a single space, so that messages
+    /// with line breaks or tabs stay on the header line
+    pub collapse_msg_whitespace: bool,
+    /// Write the errors displayed by `is_debug` to the output as comments rather than to
+    /// STDERR, so that they appear next to the lines they are about
+    pub errors_inline: bool,
+    /// Style of the keys of the extra parameters that have no style of their own in
+    /// `field_styles`
+    #[serde(skip)]
+    pub param_key_style: Option<FieldStyle>,
+    /// Style of the values of the extra parameters that have no style of their own in
+    /// `field_styles`
+    #[serde(skip)]
+    pub param_value_style: Option<FieldStyle>,
+    /// Write the hostname and src of the long format header on a continuation line when the
+    /// header is wider than `terminal_width`
+    pub wrap_header: bool,
+    /// Number of columns of the terminal the output is displayed in, if known
+    pub terminal_width: Option<usize>,
+    /// How the colorization of the output is decided. `Always` and `Never` override the
+    /// colorization of the `colored` crate once `apply_color_mode` has been called.
+    pub color_mode: ColorMode,
+    /// Name of a field holding the latency of the lines (eg `responseTime`), whose 50th, 90th
+    /// and 99th percentiles are written after the lines of the long, short and simple formats
+    pub latency_summary: Option<String>,
+    /// Time that `relative_time` displays offsets from instead of the time of the first
+    /// rendered line
+    pub anchor_time: Option<DateTime<Utc>>,
+    /// Icons displayed before the level of the long, short and simple formats, eg the ones
+    /// of [LogLevel::default_icons]
+    #[serde(skip)]
+    pub level_icons: Option<HashMap<LogLevel, String>>,
+    /// Indent of the JSON formats carrying [LogFormat::CONFIGURED_INDENT]
+    pub json_indent: i32,
+    /// Display a line only the first time its message is seen in the stream, even when
+    /// other lines were displayed in between
+    pub unique_msgs: bool,
+    /// Keys of `req` and `client_req` records displayed in the details of the line rather than
+    /// as extra parameters. Keys that aren't part of a node-bunyan request are displayed like
+    /// its body, and `method`, `url` and `httpVersion` are always written in its summary line.
+    /// Defaults to the keys of node-bunyan requests.
+    pub req_detail_fields: Option<Vec<String>>,
+    /// Keys of `res` and `client_res` records displayed in the details of the line rather than
+    /// as extra parameters. Keys that aren't part of a node-bunyan response are displayed like
+    /// its body, and `statusCode` is always written in its status line. Defaults to the keys
+    /// of node-bunyan responses.
+    pub res_detail_fields: Option<Vec<String>>,
+    /// Display the objects of the extra parameters as compact single line JSON when they
+    /// aren't longer than this width, rather than expanded in the details of the line
+    pub compact_small_objects: Option<usize>,
+    /// Display string parameters inline however long they are, ignoring
+    /// `inline_value_threshold`. Strings with line breaks are still displayed below the line.
+    pub wide: bool,
+    /// Field holding the id of the request a line was logged for (eg `reqId`), which is
+    /// displayed first among the extra parameters. Defaults to `req_id`.
+    pub req_id_field: Option<String>,
 }
 
-fn handle_error<W>(writer: &mut W, error: &Error, output_config: &LoggerOutputConfig)
+impl LoggerOutputConfig {
+    /// Returns the indent of a JSON format, which is `json_indent` if the format carries
+    /// [LogFormat::CONFIGURED_INDENT] instead of an indent of its own.
+    ///
+    /// # Arguments
+    /// * `indent` - indent carried by the JSON format
+    ///
+    pub fn resolve_json_indent(&self, indent: i32) -> i32 {
+        if indent == LogFormat::CONFIGURED_INDENT {
+            self.json_indent
+        } else {
+            indent
+        }
+    }
+
+    /// Returns the field holding the id of the request a line was logged for.
+    pub fn req_id_field(&self) -> &str {
+        self.req_id_field.as_deref().unwrap_or(DEFAULT_REQ_ID_FIELD)
+    }
+
+    /// Returns the text used to indent a single level of a nested object.
+    pub fn object_indent(&self) -> String {
+        match self.object_indent_guide {
+            Some(guide) if self.object_indent_step > 0 => format!(
+                "{}{:indent$}",
+                guide,
+                "",
+                indent = self.object_indent_step - 1
+            ),
+            _ => " ".repeat(self.object_indent_step),
+        }
+    }
+}
+
+impl Default for LoggerOutputConfig {
+    fn default() -> Self {
+        LoggerOutputConfig {
+            indent: BASE_INDENT_SIZE,
+            is_strict: false,
+            is_debug: false,
+            level: None,
+            condition_filter: None,
+            display_local_time: false,
+            format: LogFormat::Long,
+            keep_blank_lines: false,
+            component_separator: '/',
+            field_styles: FieldStyles::default(),
+            warn_unknown_version: false,
+            component_levels: HashMap::new(),
+            object_indent_step: OBJECT_INDENT_STEP,
+            object_indent_guide: None,
+            annotate_errors: false,
+            max_line_bytes: None,
+            custom_renderers: CustomRenderers::default(),
+            numeric_level: false,
+            multiline_key_on_own_line: false,
+            sort_params: false,
+            default_name: None,
+            debug_json: false,
+            limit: None,
+            level_width: LEVEL_WIDTH,
+            expand_query: false,
+            group_by: None,
+            require_fields: Vec::new(),
+            relative_time: false,
+            highlight_rules: Vec::new(),
+            src_as_hyperlink: false,
+            exclude_levels: Vec::new(),
+            inline_value_threshold: INLINE_VALUE_THRESHOLD,
+            errors_only: false,
+            status_phrases: HashMap::new(),
+            level_labels: BTreeMap::new(),
+            level_styles: BTreeMap::new(),
+            merge_window: 0,
+            tab_width: None,
+            inline_scalar_arrays: false,
+            no_trailing_newline: false,
+            compact: false,
+            divider_on_reqid_change: false,
+            show_version: false,
+            escape_control_chars: false,
+            duplicate_keys: DuplicateKeys::LastWins,
+            line_prefix: None,
+            pretty_json_bodies: false,
+            fail_fast: false,
+            fixed_time_width: false,
+            color_by_component: false,
+            slow_response_ms: None,
+            stable_inspect: false,
+            pid_filter: None,
+            time_as_epoch: false,
+            count_only: false,
+            line_ending: LineEnding::Lf,
+            truncate_trace_ids: false,
+            diff_mode: false,
+            time_brackets: true,
+            context_field: None,
+            time_fallback: TimeFallback::Reject,
+            collapse_msg_whitespace: false,
+            errors_inline: false,
+            param_key_style: None,
+            param_value_style: None,
+            wrap_header: false,
+            terminal_width: None,
+            color_mode: ColorMode::Auto,
+            latency_summary: None,
+            anchor_time: None,
+            level_icons: None,
+            json_indent: 2,
+            unique_msgs: false,
+            req_detail_fields: None,
+            res_detail_fields: None,
+            compact_small_objects: None,
+            wide: false,
+            req_id_field: None,
+        }
+    }
+}
+
+/// Reads an output configuration from the passed TOML document, so that teams can share
+/// standard views. The keys of the document are the fields of [LoggerOutputConfig], eg:
+///
+/// ```toml
+/// level = 40
+/// format = { json = 2 }
+/// line_ending = "crlf"
+/// ```
+///
+/// # Arguments
+/// * `s` - TOML document to read the configuration from
+///
+/// # Errors
+///
+/// Returns an error when the document isn't valid TOML or a key has a value of the wrong type.
+///
+pub fn config_from_toml(s: &str) -> Result<LoggerOutputConfig, toml::de::Error> {
+    toml::from_str(s)
+}
+
+/// Returns true if the output written with the passed configuration is colorized, once its
+/// `color_mode` has been resolved against the environment.
+///
+/// # Arguments
+/// * `output_config` - Configuration defining the color mode
+///
+pub fn effective_color(output_config: &LoggerOutputConfig) -> bool {
+    // The `dumb_terminal` feature disables colors whatever the color mode
+    if cfg!(feature = "dumb_terminal") {
+        return false;
+    }
+
+    resolve_color_mode(
+        output_config.color_mode,
+        |name| std::env::var(name).ok(),
+        io::stdout().is_terminal(),
+    )
+}
+
+/// Returns true if output is colorized in the passed color mode. `Auto` follows the rules of
+/// the `colored` crate: a `CLICOLOR_FORCE` other than `0` enables colors, then `NO_COLOR`
+/// disables them, and otherwise colors are enabled on terminals unless `CLICOLOR` is `0`.
+///
+/// # Arguments
+/// * `color_mode` - Color mode to resolve
+/// * `env_var` - function returning the value of the named environment variable, if set
+/// * `is_terminal` - whether the output is written to a terminal
+///
+fn resolve_color_mode<F>(color_mode: ColorMode, env_var: F, is_terminal: bool) -> bool
 where
+    F: Fn(&str) -> Option<String>,
+{
+    let is_enabled = |name: &str| env_var(name).map(|value| value != "0");
+
+    match color_mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            if is_enabled("CLICOLOR_FORCE") == Some(true) {
+                true
+            } else if env_var("NO_COLOR").is_some() {
+                false
+            } else {
+                is_terminal && is_enabled("CLICOLOR").unwrap_or(true)
+            }
+        }
+    }
+}
+
+/// Overrides the colorization of the `colored` crate if the color mode isn't `Auto`. The
+/// colorization is global to the process, so this is meant to be called once before any
+/// output is written, as the `bunyan` command does.
+///
+/// # Arguments
+/// * `output_config` - Configuration defining the color mode
+///
+pub fn apply_color_mode(output_config: &LoggerOutputConfig) {
+    if output_config.color_mode != ColorMode::Auto {
+        colored::control::set_override(effective_color(output_config));
+    }
+}
+
+fn handle_error<W>(
+    writer: &mut W,
+    error: Error,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) where
     W: Write,
 {
+    // The first error stops the stream and is returned to the caller in fail fast mode
+    if output_config.fail_fast {
+        state.error.get_or_insert(error);
+        return;
+    }
+
     if !output_config.is_strict || output_config.is_debug {
         let orig_msg = error.to_string();
 
@@ -235,21 +926,42 @@ where
             None => error.to_string(),
         };
 
-        if output_config.is_debug {
-            if let Some(column) = error.column() {
-                wln!(
-                    std::io::stderr(),
+        // serde reports the position as "<msg> at line N column M"
+        let reason = msg.strip_suffix(" at").unwrap_or(&msg);
+
+        let debug_message = if output_config.is_debug && output_config.debug_json {
+            let mut json_error: Map<String, Value> = Map::new();
+            json_error.insert("line".into(), error.line_no().into());
+            json_error.insert("column".into(), error.column().into());
+            json_error.insert("error".into(), reason.into());
+            Some(Value::Object(json_error).to_string())
+        } else if output_config.is_debug {
+            match error.column() {
+                Some(column) => Some(format!(
                     "{} on line {} column: {}",
                     msg,
                     error.line_no(),
                     column
-                );
+                )),
+                None => Some(format!("{} on line {}", msg, error.line_no())),
+            }
+        } else {
+            None
+        };
+
+        // Inline errors are written as comments so they stand apart from the log lines
+        if let Some(message) = debug_message {
+            if output_config.errors_inline {
+                wln!(writer, "{}", format!("# {}", message).dimmed());
             } else {
-                wln!(std::io::stderr(), "{} on line {}", msg, error.line_no());
+                wln!(std::io::stderr(), "{}", message);
             }
         }
 
-        if !output_config.is_strict {
+        if !output_config.is_strict && output_config.annotate_errors {
+            let annotation = format!(" # parse error: {}", reason);
+            wln!(writer, "{}{}", error.line(), annotation.dimmed());
+        } else if !output_config.is_strict {
             wln!(writer, "{}", error.line());
         }
     }
@@ -257,13 +969,15 @@ where
 
 fn write_zero_indent_json<W>(
     writer: &mut W,
-    line: String,
+    line: &[u8],
     output_config: &LoggerOutputConfig,
     line_no: usize,
+    state: &mut StreamState,
 ) where
     W: Write,
 {
-    let json_result: Result<Value, SerdeError> = serde_json::from_str(&line);
+    let json_result: Result<Value, SerdeError> =
+        duplicate_keys::from_slice(line, output_config.duplicate_keys);
     match json_result {
         Ok(value) => match serde_json::to_string(&value) {
             Ok(json) => wln!(writer, "{}", json),
@@ -272,105 +986,1539 @@ fn write_zero_indent_json<W>(
         Err(raw_error) => {
             let column: usize = raw_error.column();
             let kind = Kind::from(raw_error);
-            let error = Error::new(kind, line, line_no, Some(column));
-            handle_error(writer, &error, output_config);
+            let error = Error::new(kind, lossy_line(line), line_no, Some(column));
+            handle_error(writer, error, output_config, state);
+        }
+    }
+}
+
+/// Returns true if the passed JSON object contains all of the fields required of a bunyan
+/// log line.
+///
+/// # Arguments
+/// * `map` - JSON object to check
+///
+fn has_required_fields(map: &Map<String, Value>) -> bool {
+    REQUIRED_FIELDS.iter().all(|field| map.contains_key(*field))
+}
+
+/// Returns an error naming the first of the configured required fields missing from the
+/// passed line, if any.
+///
+/// # Arguments
+/// * `has_field` - function returning true if the line contains the named field
+/// * `output_config` - Configuration defining the required fields
+///
+fn check_required_fields<F>(has_field: F, output_config: &LoggerOutputConfig) -> ParseResult
+where
+    F: Fn(&str) -> bool,
+{
+    match output_config
+        .require_fields
+        .iter()
+        .find(|field| !has_field(field.as_str()))
+    {
+        Some(field) => Err(BunyanLogParseError::new(format!(
+            "missing required field `{}`",
+            field
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Returns true if the passed line is a JSON object containing all of the fields required
+/// of a bunyan log line. This is cheaper than a full parse because the field values are
+/// not validated or converted. Whether a field is present doesn't depend on which value of
+/// a duplicate key is kept, so the default duplicate keys policy is used.
+///
+/// # Arguments
+/// * `line` - line of text to check
+///
+pub fn is_bunyan_line(line: &str) -> bool {
+    match duplicate_keys::from_slice::<Map<String, Value>>(
+        line.as_bytes(),
+        DuplicateKeys::default(),
+    ) {
+        Ok(map) => has_required_fields(&map),
+        Err(_) => false,
+    }
+}
+
+/// Writes a warning to STDERR indicating that a log line does not use a known bunyan
+/// log format version.
+///
+/// # Arguments
+/// * `version` - value of the `v` field of the line, if present
+/// * `line_no` - line number of the line within the stream
+///
+fn warn_unknown_version(version: Option<u8>, line_no: usize) {
+    match version {
+        Some(v) => wln!(
+            std::io::stderr(),
+            "Warning: unrecognized bunyan log version [{}] on line {}",
+            v,
+            line_no
+        ),
+        None => wln!(
+            std::io::stderr(),
+            "Warning: missing bunyan log version on line {}",
+            line_no
+        ),
+    }
+}
+
+/// Returns true if the line exceeds the configured maximum line size for a format that
+/// reformats lines for display.
+///
+/// # Arguments
+/// * `line` - raw bytes of the line as read from the input
+/// * `output_config` - Configuration defining the maximum line size
+///
+fn is_oversized_line(line: &[u8], output_config: &LoggerOutputConfig) -> bool {
+    match output_config.format {
+        LogFormat::Inspect | LogFormat::Long | LogFormat::Short | LogFormat::Simple => {
+            output_config
+                .max_line_bytes
+                .is_some_and(|max_line_bytes| line.len() > max_line_bytes)
+        }
+        _ => false,
+    }
+}
+
+/// Converts the raw bytes of a line to a `String` for display purposes. Invalid UTF-8
+/// sequences are replaced rather than causing the line to be dropped.
+///
+/// # Arguments
+/// * `line` - raw bytes of the line as read from the input
+///
+fn lossy_line(line: &[u8]) -> String {
+    String::from_utf8_lossy(line).into_owned()
+}
+
+/// Removes the line terminator from a line read with `read_until`. Both `\n` and `\r\n`
+/// are stripped. A trailing `\r` is removed even when it is not followed by `\n` (as is
+/// the case for the last line of a CRLF file without a final line feed) so that it never
+/// leaks into passed through lines.
+///
+/// # Arguments
+/// * `line` - raw bytes of the line as read from the input
+///
+fn strip_line_terminator(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// Writes all of the lines of input in the configured output format.
+///
+/// # Errors
+///
+/// In `fail_fast` mode, returns the error of the first line that can't be parsed or
+/// rendered. No more input is read after that line.
+///
+pub fn write_bunyan_output<W, R>(
+    writer: &mut W,
+    reader: R,
+    output_config: &LoggerOutputConfig,
+) -> Result<(), ViewError>
+where
+    W: Write,
+    R: BufRead,
+{
+    write_output_with_state(
+        writer,
+        reader,
+        output_config,
+        StreamState::new(output_config),
+    )
+    .map_err(ViewError::from)
+}
+
+/// Writes all of the lines of input in the configured output format like
+/// `write_bunyan_output`, invoking `on_line` for each line parsed as a bunyan log line.
+/// The callback is invoked before the level and condition filters are applied, so it also
+/// sees the lines that are filtered out of the output.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `reader` - BufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the output format
+/// * `on_line` - callback invoked with each parsed log line
+///
+/// # Errors
+///
+/// In `fail_fast` mode, returns the error of the first line that can't be parsed or
+/// rendered.
+///
+pub fn write_bunyan_output_with<W, R, F>(
+    writer: &mut W,
+    reader: R,
+    output_config: &LoggerOutputConfig,
+    mut on_line: F,
+) -> Result<(), ViewError>
+where
+    W: Write,
+    R: BufRead,
+    F: FnMut(&BunyanLine),
+{
+    let state = StreamState {
+        on_line: Some(&mut on_line),
+        ..StreamState::new(output_config)
+    };
+    write_output_with_state(writer, reader, output_config, state).map_err(ViewError::from)
+}
+
+/// Writes each line of input in the configured output format to one of two writers
+/// depending on its level, eg to separate errors from the rest of the log. Lines at or above
+/// `threshold` are written to `error_writer`, other lines (including the ones that can't be
+/// parsed) to `normal_writer`. The line ending, line prefix and trailing newline options
+/// apply to both writers.
+///
+/// # Arguments
+/// * `error_writer` - Write implementation receiving the lines at or above the threshold
+/// * `normal_writer` - Write implementation receiving the other lines
+/// * `reader` - BufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the output format
+/// * `threshold` - lowest level of the lines written to `error_writer`
+///
+/// # Errors
+///
+/// Lines are routed one at a time, so an error is returned without reading any input when
+/// an output written once the whole input has been read is configured (`group_by`,
+/// `merge_window`, `count_only`, `latency_summary` or the JSON array format).
+///
+/// In `fail_fast` mode, returns the error of the first line that can't be parsed or
+/// rendered. No more input is read after that line.
+///
+/// Returns the error of the first write that fails on either writer. No more input is read
+/// after it.
+///
+pub fn write_bunyan_split<W1, W2, R>(
+    error_writer: &mut W1,
+    normal_writer: &mut W2,
+    reader: R,
+    output_config: &LoggerOutputConfig,
+    threshold: u16,
+) -> Result<(), ViewError>
+where
+    W1: Write,
+    W2: Write,
+    R: BufRead,
+{
+    if is_buffered_output(output_config) {
+        return Err(ViewError::ValidationError {
+            message: "group_by, merge_window, count_only, latency_summary and the JSON array \
+                      format can't be used when splitting the output"
+                .to_string(),
+            line_no: 0,
+        });
+    }
+
+    let error_writer = &mut LineEndingWriter::new(error_writer, output_config.line_ending);
+    let normal_writer = &mut LineEndingWriter::new(normal_writer, output_config.line_ending);
+
+    if output_config.no_trailing_newline {
+        write_prefixed_split(
+            &mut TrailingNewlineWriter::new(error_writer),
+            &mut TrailingNewlineWriter::new(normal_writer),
+            reader,
+            output_config,
+            threshold,
+        )
+    } else {
+        write_prefixed_split(
+            error_writer,
+            normal_writer,
+            reader,
+            output_config,
+            threshold,
+        )
+    }
+}
+
+/// Routes all of the lines of input to one of two writers depending on their level,
+/// starting each line of output with the configured line prefix if there is one. Returns
+/// the error that stopped the stream in fail fast mode or the error of a failed write.
+///
+/// # Arguments
+/// * `error_writer` - Write implementation receiving the lines at or above the threshold
+/// * `normal_writer` - Write implementation receiving the other lines
+/// * `reader` - BufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the output format and line prefix
+/// * `threshold` - lowest level of the lines written to `error_writer`
+///
+fn write_prefixed_split<W1, W2, R>(
+    error_writer: &mut W1,
+    normal_writer: &mut W2,
+    reader: R,
+    output_config: &LoggerOutputConfig,
+    threshold: u16,
+) -> Result<(), ViewError>
+where
+    W1: Write,
+    W2: Write,
+    R: BufRead,
+{
+    match &output_config.line_prefix {
+        Some(prefix) => write_split_stream(
+            &mut LinePrefixWriter::new(error_writer, prefix),
+            &mut LinePrefixWriter::new(normal_writer, prefix),
+            reader,
+            output_config,
+            threshold,
+        ),
+        None => write_split_stream(
+            error_writer,
+            normal_writer,
+            reader,
+            output_config,
+            threshold,
+        ),
+    }
+}
+
+/// Routes all of the lines of input to one of two writers depending on their level. Each
+/// line is rendered before being routed, and its level is read from the parsed line passed
+/// to the line callback of the stream, so that it is only parsed once. Returns the error
+/// that stopped the stream in fail fast mode or the error of a failed write, after which no
+/// more input is read.
+///
+/// # Arguments
+/// * `error_writer` - Write implementation receiving the lines at or above the threshold
+/// * `normal_writer` - Write implementation receiving the other lines
+/// * `reader` - BufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the output format
+/// * `threshold` - lowest level of the lines written to `error_writer`
+///
+fn write_split_stream<W1, W2, R>(
+    error_writer: &mut W1,
+    normal_writer: &mut W2,
+    mut reader: R,
+    output_config: &LoggerOutputConfig,
+    threshold: u16,
+) -> Result<(), ViewError>
+where
+    W1: Write,
+    W2: Write,
+    R: BufRead,
+{
+    let level: Cell<Option<u16>> = Cell::new(None);
+    let mut on_line = |log: &BunyanLine| level.set(Some(log.level));
+    let mut state = StreamState {
+        on_line: Some(&mut on_line),
+        ..StreamState::new(output_config)
+    };
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut rendered: Vec<u8> = Vec::new();
+    let mut line_no: usize = 0;
+    let mut rendered_lines: usize = 0;
+
+    while !is_stream_done(rendered_lines, output_config, &state)
+        && read_line(&mut reader, &mut buffer)
+    {
+        line_no += 1;
+        level.set(None);
+        rendered.clear();
+
+        if write_bunyan_line(&mut rendered, &buffer, line_no, output_config, &mut state) {
+            rendered_lines += 1;
         }
+
+        let writer: &mut dyn Write = if level.get().is_some_and(|level| level >= threshold) {
+            error_writer
+        } else {
+            normal_writer
+        };
+
+        writer.write_all(&rendered)?;
+    }
+
+    match state.error {
+        Some(error) => Err(ViewError::from(error)),
+        None => Ok(()),
     }
 }
 
-pub fn write_bunyan_output<W, R>(writer: &mut W, reader: R, output_config: &LoggerOutputConfig)
+/// Returns true if the configured output needs the whole input before writing anything.
+///
+/// # Arguments
+/// * `output_config` - Configuration defining the output format
+///
+fn is_buffered_output(output_config: &LoggerOutputConfig) -> bool {
+    matches!(output_config.format, LogFormat::JsonArray(_))
+        || output_config.count_only
+        || output_config.latency_summary.is_some()
+        || output_config.group_by.is_some()
+        || output_config.merge_window > 0
+}
+
+/// Writes all of the lines of input starting from the passed stream state.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `reader` - BufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the output format
+/// * `state` - initial state of the stream
+///
+fn write_output_with_state<W, R>(
+    writer: &mut W,
+    reader: R,
+    output_config: &LoggerOutputConfig,
+    mut state: StreamState,
+) -> Result<(), Error>
 where
     W: Write,
     R: BufRead,
 {
+    let writer = &mut LineEndingWriter::new(writer, output_config.line_ending);
+
+    if output_config.no_trailing_newline {
+        write_prefixed_stream(
+            &mut TrailingNewlineWriter::new(writer),
+            reader,
+            output_config,
+            &mut state,
+        );
+    } else {
+        write_prefixed_stream(writer, reader, output_config, &mut state);
+    }
+
+    match state.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Writes all of the lines of input, starting each line of output with the configured
+/// line prefix if there is one.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `reader` - BufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the output format and line prefix
+/// * `state` - state carried from one line of the stream to the next
+///
+fn write_prefixed_stream<W, R>(
+    writer: &mut W,
+    reader: R,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) where
+    W: Write,
+    R: BufRead,
+{
+    match &output_config.line_prefix {
+        Some(prefix) => write_stream(
+            &mut LinePrefixWriter::new(writer, prefix),
+            reader,
+            output_config,
+            state,
+        ),
+        None => write_stream(writer, reader, output_config, state),
+    }
+
+    if let Some(field) = &output_config.latency_summary {
+        write_latency_summary(writer, field, &mut state.latencies);
+    }
+}
+
+/// Returns the latency logged in the passed field of the line, in milliseconds as a number
+/// or a numeric string. Other values aren't latencies.
+///
+/// # Arguments
+/// * `log` - log line to read the latency from
+/// * `field` - name of the field holding the latency
+///
+fn latency_value(log: &BunyanLine, field: &str) -> Option<f64> {
+    match log.other.get(field)? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(string) => string.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+    .filter(|latency| latency.is_finite())
+}
+
+/// Writes the 50th, 90th and 99th percentiles of the passed latencies on a line of its own,
+/// using the nearest rank method.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `field` - name of the field the latencies were read from
+/// * `latencies` - latencies of the stream, which are sorted in place
+///
+fn write_latency_summary<W: Write>(writer: &mut W, field: &str, latencies: &mut [f64]) {
+    if latencies.is_empty() {
+        wln!(writer, "{} latency: no values", field);
+        return;
+    }
+
+    latencies.sort_by(f64::total_cmp);
+
+    let percentile = |p: usize| {
+        let rank = (p * latencies.len()).div_ceil(100);
+        latencies[rank.max(1) - 1]
+    };
+
+    wln!(
+        writer,
+        "{} latency: p50={} p90={} p99={} ({} values)",
+        field,
+        percentile(50),
+        percentile(90),
+        percentile(99),
+        latencies.len()
+    );
+}
+
+/// Writes all of the lines of input in the configured output format.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `reader` - BufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the output format
+/// * `state` - state carried from one line of the stream to the next
+///
+fn write_stream<W, R>(
+    writer: &mut W,
+    mut reader: R,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) where
+    W: Write,
+    R: BufRead,
+{
+    if output_config.count_only {
+        write_line_count(writer, reader, output_config, state);
+        return;
+    }
+
+    if let LogFormat::JsonArray(indent) = output_config.format {
+        let indent = output_config.resolve_json_indent(indent);
+        write_json_array_output(writer, reader, indent, output_config, state);
+        return;
+    }
+
+    if let Some(field) = &output_config.group_by {
+        write_grouped_output(writer, reader, field, output_config, state);
+        return;
+    }
+
+    if output_config.merge_window > 0 {
+        let window = output_config.merge_window;
+        write_reordered_output(writer, reader, window, output_config, state);
+        return;
+    }
+
     let mut line_no: usize = 0;
+    // Lines are read as raw bytes so that serde can parse them directly without
+    // first validating them as UTF-8 strings
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut rendered_lines: usize = 0;
+
+    while !is_stream_done(rendered_lines, output_config, state)
+        && read_line(&mut reader, &mut buffer)
+    {
+        line_no += 1;
+        if write_bunyan_line(writer, &buffer, line_no, output_config, state) {
+            rendered_lines += 1;
+        }
+    }
+}
+
+/// State carried from one line of a stream to the next
+struct StreamState<'a> {
+    /// Whether the unknown version warning was already emitted
+    has_warned_version: bool,
+    /// Time of the first rendered line, the origin of relative times
+    first_time: Option<DateTime<Utc>>,
+    /// `req_id` of the last rendered line, `Null` when it had none
+    previous_req_id: Option<Value>,
+    /// Callback of embedders invoked for each parsed log line
+    on_line: Option<&'a mut dyn FnMut(&BunyanLine)>,
+    /// First error of the stream in fail fast mode, after which no more input is read
+    error: Option<Error>,
+    /// Whether any level, pid or condition filter is configured
+    has_line_filters: bool,
+    /// Extra fields of the last rendered line, which `diff_mode` compares lines against
+    previous_fields: Option<Map<String, Value>>,
+    /// Values of the `latency_summary` field of the lines that passed the filters
+    latencies: Vec<f64>,
+    /// Messages of the lines that passed the filters, when `unique_msgs` is set
+    seen_msgs: HashSet<String>,
+}
+
+impl StreamState<'_> {
+    /// Returns the state of a stream that hasn't written any line yet.
+    ///
+    /// # Arguments
+    /// * `output_config` - Configuration defining the filters
+    ///
+    fn new(output_config: &LoggerOutputConfig) -> Self {
+        StreamState {
+            has_warned_version: false,
+            first_time: None,
+            previous_req_id: None,
+            on_line: None,
+            error: None,
+            has_line_filters: has_line_filters(output_config),
+            previous_fields: None,
+            latencies: Vec::new(),
+            seen_msgs: HashSet::new(),
+        }
+    }
+}
+
+/// Invokes the line callback of the stream with the passed parsed line, if there is a
+/// callback and the line is a valid bunyan log line.
+///
+/// # Arguments
+/// * `map` - JSON object the line was parsed into
+/// * `output_config` - Configuration defining the time fallback
+/// * `state` - state of the stream holding the callback
+///
+fn notify_line(
+    map: &Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) {
+    if let Some(on_line) = state.on_line.as_deref_mut() {
+        if let Some(log) = bunyan_line_from_map(map.clone(), output_config) {
+            on_line(&log);
+        }
+    }
+}
+
+/// Returns true if lines can be hidden by their level, their component threshold, their pid
+/// or the condition filter. Without any filter, lines are written without evaluating them.
+///
+/// # Arguments
+/// * `output_config` - Configuration defining the filters
+///
+fn has_line_filters(output_config: &LoggerOutputConfig) -> bool {
+    output_config.level.is_some()
+        || !output_config.component_levels.is_empty()
+        || !output_config.exclude_levels.is_empty()
+        || output_config.condition_filter.is_some()
+        || output_config.pid_filter.is_some()
+}
+
+/// Returns true if the passed log line passes the configured level, pid and condition filters.
+///
+/// # Arguments
+/// * `log` - parsed log line
+/// * `line` - raw bytes of the line, evaluated by the condition filter
+/// * `output_config` - Configuration defining the filters
+///
+fn is_line_shown(log: &BunyanLine, line: &[u8], output_config: &LoggerOutputConfig) -> bool {
+    // Components with their own threshold are exempt from the global level
+    let level_threshold = log
+        .component
+        .as_ref()
+        .and_then(|component| output_config.component_levels.get(component))
+        .copied()
+        .or(output_config.level);
+
+    let is_level_shown = match level_threshold {
+        Some(output_level) => output_level <= log.level,
+        None => true,
+    } && !output_config.exclude_levels.contains(&log.level);
+
+    let is_pid_shown = output_config.pid_filter.map_or(true, |pid| pid == log.pid);
+
+    is_level_shown
+        && is_pid_shown
+        && match &output_config.condition_filter {
+            Some(condition_filter) => condition_filter.filter(&String::from_utf8_lossy(line)),
+            None => true,
+        }
+}
+
+/// Returns true if the passed log line passes the configured filters and, when only unique
+/// messages are shown, its message wasn't seen before in the stream.
+///
+/// # Arguments
+/// * `log` - parsed log line
+/// * `line` - raw bytes of the line, evaluated by the condition filter
+/// * `output_config` - Configuration defining the filters
+/// * `state` - state of the stream holding the messages seen so far
+///
+fn is_line_written(
+    log: &BunyanLine,
+    line: &[u8],
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) -> bool {
+    (!state.has_line_filters || is_line_shown(log, line, output_config))
+        && (!output_config.unique_msgs || state.seen_msgs.insert(log.msg.clone()))
+}
+
+/// Parses the passed line as a bunyan log line, resolving duplicate keys with the configured
+/// policy.
+///
+/// # Arguments
+/// * `line` - raw bytes of the line without its line terminator
+/// * `output_config` - Configuration defining how duplicate keys are parsed
+///
+fn parse_bunyan_line(
+    line: &[u8],
+    output_config: &LoggerOutputConfig,
+) -> Result<BunyanLine, SerdeError> {
+    match duplicate_keys::from_slice(line, output_config.duplicate_keys) {
+        Err(e) if e.is_data() && output_config.time_fallback != TimeFallback::Reject => {
+            parse_line_without_time(line, output_config).ok_or(e)
+        }
+        result => result,
+    }
+}
+
+/// Parses the passed line as a log line whose missing time is replaced according to the
+/// configured `time_fallback`. Returns None if the line has a time or is invalid for another
+/// reason.
+///
+/// # Arguments
+/// * `line` - raw bytes of the line without its line terminator
+/// * `output_config` - Configuration defining the time fallback and duplicate keys policy
+///
+fn parse_line_without_time(line: &[u8], output_config: &LoggerOutputConfig) -> Option<BunyanLine> {
+    let map: Map<String, Value> =
+        duplicate_keys::from_slice(line, output_config.duplicate_keys).ok()?;
+
+    if has_time(&map) {
+        return None;
+    }
+
+    line_with_fallback_time(map, output_config)
+}
+
+/// Returns true if the passed JSON object has a time, under [time] or one of its aliases.
+///
+/// # Arguments
+/// * `map` - JSON object of a log line
+///
+fn has_time(map: &Map<String, Value>) -> bool {
+    map.contains_key("time") || TIME_ALIASES.iter().any(|alias| map.contains_key(*alias))
+}
+
+/// Converts the passed JSON object without a time into a log line whose time is replaced
+/// according to the configured `time_fallback`. Returns None if it isn't a valid log line.
+///
+/// # Arguments
+/// * `map` - JSON object of a log line without a time
+/// * `output_config` - Configuration defining the time fallback
+///
+fn line_with_fallback_time(
+    mut map: Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) -> Option<BunyanLine> {
+    let time = match output_config.time_fallback {
+        TimeFallback::Now => Utc::now(),
+        _ => DateTime::UNIX_EPOCH,
+    };
+    map.insert(
+        "time".to_string(),
+        Value::String(time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+    );
+
+    let mut log: BunyanLine = serde_json::from_value(Value::Object(map)).ok()?;
+    log.is_time_unknown = output_config.time_fallback == TimeFallback::Unknown;
+    log.has_fallback_time = true;
+    Some(log)
+}
+
+/// Converts the passed parsed JSON object into a log line, replacing a missing time according
+/// to the configured `time_fallback`. Returns None if it isn't a valid log line.
+///
+/// # Arguments
+/// * `map` - JSON object of a log line
+/// * `output_config` - Configuration defining the time fallback
+///
+fn bunyan_line_from_map(
+    map: Map<String, Value>,
+    output_config: &LoggerOutputConfig,
+) -> Option<BunyanLine> {
+    if has_time(&map) || output_config.time_fallback == TimeFallback::Reject {
+        serde_json::from_value(Value::Object(map)).ok()
+    } else {
+        line_with_fallback_time(map, output_config)
+    }
+}
+
+/// Returns true once `limit` log lines have been rendered or an error stopped the stream in
+/// fail fast mode, after which no more input is read.
+///
+/// # Arguments
+/// * `rendered_lines` - Number of log lines rendered so far
+/// * `output_config` - Configuration defining the limit
+/// * `state` - state of the stream holding the fail fast error
+///
+fn is_stream_done(
+    rendered_lines: usize,
+    output_config: &LoggerOutputConfig,
+    state: &StreamState,
+) -> bool {
+    state.error.is_some()
+        || output_config
+            .limit
+            .is_some_and(|limit| rendered_lines >= limit)
+}
+
+/// Reads the next line of input including its line terminator into `buffer`, replacing
+/// its previous content. Returns false once the end of input has been reached.
+///
+/// # Arguments
+/// * `reader` - BufRead implementation to read the line from
+/// * `buffer` - buffer receiving the raw bytes of the line
+///
+fn read_line<R: BufRead>(reader: &mut R, buffer: &mut Vec<u8>) -> bool {
+    buffer.clear();
+
+    match reader.read_until(b'\n', buffer) {
+        Ok(0) => false,
+        Ok(_) => true,
+        Err(e) => {
+            panic!("{}", e);
+        }
+    }
+}
+
+/// Raw bytes of a buffered line along with its line number in the input
+type NumberedLine = (usize, Vec<u8>);
+
+/// Returns the text identifying the group of a line, or `None` when the line isn't
+/// a JSON object containing the field. String values are used without quotes.
+///
+/// # Arguments
+/// * `line` - raw bytes of the line as read from the input
+/// * `field` - name of the field whose value groups lines
+/// * `output_config` - Configuration defining how duplicate keys are parsed
+///
+fn group_key(line: &[u8], field: &str, output_config: &LoggerOutputConfig) -> Option<String> {
+    let map: Map<String, Value> =
+        duplicate_keys::from_slice(line.trim_ascii(), output_config.duplicate_keys).ok()?;
+
+    match map.get(field)? {
+        Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Reads all of the input and writes the number of lines that pass the filters on a line of
+/// its own. Lines that can't be parsed aren't written nor counted.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `reader` - BufRead implementation to read the lines from
+/// * `output_config` - Configuration defining the filters
+/// * `state` - state carried from one line of the stream to the next
+///
+fn write_line_count<W, R>(
+    writer: &mut W,
+    mut reader: R,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) where
+    W: Write,
+    R: BufRead,
+{
+    let mut line_no: usize = 0;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut count: usize = 0;
+
+    while !is_stream_done(count, output_config, state) && read_line(&mut reader, &mut buffer) {
+        line_no += 1;
+        if write_bunyan_line(&mut io::sink(), &buffer, line_no, output_config, state) {
+            count += 1;
+        }
+    }
+
+    wln!(writer, "{}", count);
+}
+
+/// Buffers all of the input and writes the lines grouped by the value of `field`. Each
+/// group is written under a banner in the order its first line appeared, followed by the
+/// lines without the field.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `reader` - BufRead implementation to read the lines from
+/// * `field` - name of the field whose value groups lines
+/// * `output_config` - Configuration defining the output format
+/// * `state` - state carried from one line of the stream to the next
+///
+fn write_grouped_output<W, R>(
+    writer: &mut W,
+    mut reader: R,
+    field: &str,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) where
+    W: Write,
+    R: BufRead,
+{
+    let mut line_no: usize = 0;
+    let mut buffer: Vec<u8> = Vec::new();
+    // Groups are kept in order of appearance along with the line numbers of their lines,
+    // and indexed by their key
+    let mut groups: Vec<(String, Vec<NumberedLine>)> = Vec::new();
+    let mut group_indexes: HashMap<String, usize> = HashMap::new();
+    let mut ungrouped: Vec<NumberedLine> = Vec::new();
+
+    while read_line(&mut reader, &mut buffer) {
+        line_no += 1;
+        let line = (line_no, buffer.clone());
+
+        match group_key(&buffer, field, output_config) {
+            Some(key) => match group_indexes.get(&key) {
+                Some(index) => groups[*index].1.push(line),
+                None => {
+                    group_indexes.insert(key.clone(), groups.len());
+                    groups.push((key, vec![line]));
+                }
+            },
+            None => ungrouped.push(line),
+        }
+    }
+
+    let sections = groups
+        .into_iter()
+        .map(|(key, lines)| (format!("{}={}", field, key), lines))
+        .chain(Some(("ungrouped".to_string(), ungrouped)))
+        .filter(|(_, lines)| !lines.is_empty());
+
+    let mut rendered_lines: usize = 0;
+
+    for (title, lines) in sections {
+        if is_stream_done(rendered_lines, output_config, state) {
+            break;
+        }
+
+        wln!(writer, "=== {} ===", title);
+
+        for (line_no, line) in lines {
+            if is_stream_done(rendered_lines, output_config, state) {
+                break;
+            }
+
+            if write_bunyan_line(writer, &line, line_no, output_config, state) {
+                rendered_lines += 1;
+            }
+        }
+    }
+}
+
+/// Buffers all of the input and writes the lines as the elements of a single JSON array
+/// indented by `indent` spaces, or on a single line if `indent` is less than 1. Lines that
+/// can't be parsed as JSON are handled as errors before the array is written.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `reader` - BufRead implementation to read the lines from
+/// * `indent` - number of spaces each level of the array is indented by
+/// * `output_config` - Configuration defining how errors are handled
+/// * `state` - state of the stream holding the line callback
+///
+fn write_json_array_output<W, R>(
+    writer: &mut W,
+    mut reader: R,
+    indent: i32,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) where
+    W: Write,
+    R: BufRead,
+{
+    let mut line_no: usize = 0;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut values: Vec<Value> = Vec::new();
+
+    while !is_stream_done(values.len(), output_config, state) && read_line(&mut reader, &mut buffer)
+    {
+        line_no += 1;
+        let line = strip_line_terminator(&buffer).trim_ascii();
+
+        // Blank lines have no place in the array
+        if line.is_empty() {
+            continue;
+        }
+
+        match duplicate_keys::from_slice::<Value>(line, output_config.duplicate_keys) {
+            Ok(value) => {
+                if let Value::Object(map) = &value {
+                    notify_line(map, output_config, state);
+                }
+
+                // Lines that aren't bunyan log lines can't pass the filters
+                let is_filtered = state.has_line_filters || output_config.unique_msgs;
+                let is_written = !is_filtered
+                    || match &value {
+                        Value::Object(map) => bunyan_line_from_map(map.clone(), output_config)
+                            .is_some_and(|log| is_line_written(&log, line, output_config, state)),
+                        _ => false,
+                    };
+
+                if is_written {
+                    values.push(value);
+                }
+            }
+            Err(raw_error) => {
+                let column: usize = raw_error.column();
+                let kind = Kind::from(raw_error);
+                let error = Error::new(kind, lossy_line(line), line_no, Some(column));
+                handle_error(writer, error, output_config, state);
+            }
+        }
+    }
+
+    // The array is left unwritten when the stream stopped at an error in fail fast mode
+    if state.error.is_some() {
+        return;
+    }
+
+    match to_indented_json(&Value::Array(values), indent) {
+        Ok(json) => wln!(writer, "{}", json),
+        Err(_) => panic!("Unable to write json string"),
+    }
+}
+
+/// Returns the passed value as JSON indented by `indent` spaces, or on a single line if
+/// `indent` is less than 1.
+///
+/// # Arguments
+/// * `value` - JSON value to convert to a string
+/// * `indent` - number of spaces each level of the value is indented by
+///
+fn to_indented_json(value: &Value, indent: i32) -> Result<String, SerdeError> {
+    if indent < 1 {
+        return serde_json::to_string(value);
+    }
+
+    let indent = " ".repeat(indent as usize);
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut pretty: Vec<u8> = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut pretty, formatter);
+
+    value
+        .serialize(&mut serializer)
+        .map(|_| String::from_utf8_lossy(&pretty).into_owned())
+}
+
+/// Returns the passed log line rendered in the configured output format, including the
+/// trailing line feed. Lines are rendered regardless of the level and condition filters.
+/// The JSON and passthrough formats render the line as canonical JSON (see
+/// `BunyanLine::to_json`) because the text it was parsed from isn't available.
+///
+/// # Arguments
+/// * `line` - log line to render
+/// * `output_config` - Configuration defining the output format
+///
+/// # Errors
+///
+/// Returns an error if the data of the line isn't in the structure expected by the format.
+///
+/// # Example
+///
+/// ```
+/// use bunyan_view::{BunyanLine, LogFormat, LoggerOutputConfig};
+///
+/// let line: BunyanLine = serde_json::from_str(
+///     r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}"#,
+/// )
+/// .unwrap();
+/// let output_config = LoggerOutputConfig {
+///     format: LogFormat::Short,
+///     ..LoggerOutputConfig::default()
+/// };
+///
+/// assert_eq!(
+///     bunyan_view::format_line_to_string(&line, &output_config).unwrap(),
+///     "22:56:52.856Z  INFO myservice: My message\n"
+/// );
+/// ```
+pub fn format_line_to_string(
+    line: &BunyanLine,
+    output_config: &LoggerOutputConfig,
+) -> Result<String, ViewError> {
+    // The formatted line is the only line, so its errors are reported on line 1
+    render_line_to_string(line, output_config).map_err(|e| ViewError::from_render_error(e, 1))
+}
+
+/// Renders the passed log line in the configured output format, like
+/// `format_line_to_string` does.
+///
+/// # Arguments
+/// * `line` - parsed log line
+/// * `output_config` - Configuration defining the output format
+///
+fn render_line_to_string(
+    line: &BunyanLine,
+    output_config: &LoggerOutputConfig,
+) -> Result<String, BunyanLogParseError> {
+    let mut buffer: Vec<u8> = Vec::new();
+
+    match output_config.format {
+        LogFormat::Json(indent) | LogFormat::JsonArray(indent) => {
+            let indent = output_config.resolve_json_indent(indent);
+            let json = to_indented_json(&line.to_json(), indent)
+                .map_err(|e| BunyanLogParseError::new(e.to_string()))?;
+            wln!(buffer, "{}", json);
+        }
+        LogFormat::Passthrough => wln!(buffer, "{}", line.to_json()),
+        LogFormat::Inspect => match line.to_json() {
+            Value::Object(map) => write_inspect_line(&mut buffer, map, output_config),
+            _ => unreachable!("log lines are always serialized as objects"),
+        },
+        _ => output_config
+            .format
+            .write_log(&mut buffer, line.clone(), output_config)?,
+    }
+
+    let mut converted: Vec<u8> = Vec::new();
+    LineEndingWriter::new(&mut converted, output_config.line_ending)
+        .write_all(&buffer)
+        .map_err(|e| BunyanLogParseError::new(e.to_string()))?;
+
+    String::from_utf8(converted).map_err(|e| BunyanLogParseError::new(e.to_string()))
+}
+
+/// Parses all of the lines of input as bunyan log lines, eg to process a whole file at once.
+/// Returns the lines that were parsed, in input order, along with the errors of the lines
+/// that couldn't be parsed. Blank lines are skipped. Reading stops at the first I/O error,
+/// which is returned last.
+///
+/// # Arguments
+/// * `reader` - BufRead implementation to read the lines from
+///
+pub fn parse_all<R: BufRead>(mut reader: R) -> (Vec<BunyanLine>, Vec<ViewError>) {
+    let output_config = LoggerOutputConfig::default();
+    let mut lines: Vec<BunyanLine> = Vec::new();
+    let mut errors: Vec<ViewError> = Vec::new();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut line_no: usize = 0;
+
+    loop {
+        buffer.clear();
+
+        match reader.read_until(b'\n', &mut buffer) {
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(e) => {
+                errors.push(ViewError::from(e));
+                break;
+            }
+        }
+
+        line_no += 1;
+
+        let line = strip_line_terminator(&buffer).trim_ascii();
+        if line.is_empty() {
+            continue;
+        }
+
+        match parse_bunyan_line(line, &output_config) {
+            Ok(log) => lines.push(log),
+            Err(raw_error) => {
+                let column: usize = raw_error.column();
+                let kind = Kind::from(raw_error);
+                let error = Error::new(kind, lossy_line(line), line_no, Some(column));
+                errors.push(ViewError::from(error));
+            }
+        }
+    }
+
+    (lines, errors)
+}
+
+/// Writes the lines of the passed range in the configured output format, eg the page of a
+/// scrollable viewer. Each line is rendered like `format_line_to_string` renders it. The
+/// range is clamped to the passed lines.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `lines` - parsed log lines
+/// * `range` - indices of the lines to render
+/// * `output_config` - Configuration defining the output format
+///
+/// # Errors
+///
+/// Returns the error of the first line that can't be rendered or written. The lines before
+/// it have already been written.
+///
+pub fn write_lines<W: Write>(
+    writer: &mut W,
+    lines: &[BunyanLine],
+    range: Range<usize>,
+    output_config: &LoggerOutputConfig,
+) -> Result<(), ViewError> {
+    let end = range.end.min(lines.len());
+    let start = range.start.min(end);
+
+    for (index, line) in lines[start..end].iter().enumerate() {
+        // Lines are numbered by their position in `lines`, starting at 1
+        let text = render_line_to_string(line, output_config)
+            .map_err(|e| ViewError::from_render_error(e, start + index + 1))?;
+        writer.write_all(text.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Handles the passed line as an error if it isn't a valid bunyan log line or is missing
+/// one of the required fields. Valid lines are not written.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `line` - raw bytes of the line without its line terminator
+/// * `line_no` - line number of the line in the input
+/// * `output_config` - Configuration defining how errors are handled
+/// * `state` - state of the stream receiving the error in fail fast mode
+///
+fn write_invalid_line<W: Write>(
+    writer: &mut W,
+    line: &[u8],
+    line_no: usize,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) {
+    let error = match parse_bunyan_line(line, output_config) {
+        Ok(log) => match check_required_fields(|field| log.has_field(field), output_config) {
+            Ok(_) => return,
+            Err(e) => Error::new(Kind::from(e), lossy_line(line), line_no, None),
+        },
+        Err(raw_error) => {
+            let column: usize = raw_error.column();
+            let kind = Kind::from(raw_error);
+            Error::new(kind, lossy_line(line), line_no, Some(column))
+        }
+    };
+
+    handle_error(writer, error, output_config, state);
+}
+
+/// Returns the time of a line, or `None` when the line isn't a JSON object with a valid
+/// [time] or time alias.
+///
+/// # Arguments
+/// * `line` - raw bytes of the line as read from the input
+/// * `output_config` - Configuration defining how duplicate keys are parsed
+///
+fn line_time(line: &[u8], output_config: &LoggerOutputConfig) -> Option<DateTime<Utc>> {
+    let map: Map<String, Value> =
+        duplicate_keys::from_slice(line.trim_ascii(), output_config.duplicate_keys).ok()?;
+
+    let value = map
+        .get("time")
+        .or_else(|| TIME_ALIASES.iter().find_map(|alias| map.get(*alias)))?;
+
+    date_deserializer::parse_json_timestamp(value).ok()
+}
+
+/// Writes the lines of input ordered by time using a bounded reorder window. Up to `window`
+/// lines are buffered and the earliest buffered line is written each time a new line is
+/// read, so lines that are at most `window` lines out of place are written in order while
+/// memory use stays bounded. Lines without a time keep the time of the line before them.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `reader` - BufRead implementation to read the lines from
+/// * `window` - maximum number of lines buffered
+/// * `output_config` - Configuration defining the output format
+/// * `state` - state carried from one line of the stream to the next
+///
+fn write_reordered_output<W, R>(
+    writer: &mut W,
+    mut reader: R,
+    window: usize,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) where
+    W: Write,
+    R: BufRead,
+{
+    let mut line_no: usize = 0;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut rendered_lines: usize = 0;
+    let mut last_time: Option<DateTime<Utc>> = None;
+    // Lines are ordered by time and then by line number so that ties keep their input order
+    let mut pending: BinaryHeap<Reverse<(Option<DateTime<Utc>>, NumberedLine)>> = BinaryHeap::new();
+
+    loop {
+        let is_read = !is_stream_done(rendered_lines, output_config, state)
+            && read_line(&mut reader, &mut buffer);
+
+        if is_read {
+            line_no += 1;
+            last_time = line_time(&buffer, output_config).or(last_time);
+            pending.push(Reverse((last_time, (line_no, buffer.clone()))));
+
+            if pending.len() <= window {
+                continue;
+            }
+        }
+
+        let Some(Reverse((_, (line_no, line)))) = pending.pop() else {
+            break;
+        };
+
+        if is_stream_done(rendered_lines, output_config, state) {
+            break;
+        }
+
+        if write_bunyan_line(writer, &line, line_no, output_config, state) {
+            rendered_lines += 1;
+        }
+    }
+}
+
+/// Writes a single line of input in the configured output format. Returns true if the line
+/// was rendered as a log line, as opposed to being skipped, filtered out or passed through
+/// because it couldn't be parsed.
+///
+/// # Arguments
+/// * `writer` - Write implementation to output data to
+/// * `line` - raw bytes of the line as read from the input
+/// * `line_no` - line number of the line in the input
+/// * `output_config` - Configuration defining the output format
+/// * `state` - state carried over from the previous lines of the stream
+///
+fn write_bunyan_line<W>(
+    writer: &mut W,
+    line: &[u8],
+    line_no: usize,
+    output_config: &LoggerOutputConfig,
+    state: &mut StreamState,
+) -> bool
+where
+    W: Write,
+{
     let format = &output_config.format;
+    let mut is_rendered = false;
+    let raw_line = line;
+    let line = strip_line_terminator(line);
+    let trimmed = line.trim_ascii_start();
+
+    // Only lines that can't be parsed are displayed, so blank lines are skipped as well
+    if output_config.errors_only {
+        if !trimmed.trim_ascii_end().is_empty() {
+            write_invalid_line(writer, trimmed, line_no, output_config, state);
+        }
+    // Don't process empty lines because the output isn't useful to our users
+    } else if (!output_config.is_strict || output_config.keep_blank_lines)
+        && trimmed.trim_ascii_end().is_empty()
+    {
+        wln!(writer);
+    } else if let LogFormat::Json(indent) = format {
+        let indent = output_config.resolve_json_indent(*indent);
+        let json_result: Result<Value, SerdeError> =
+            duplicate_keys::from_slice(trimmed, output_config.duplicate_keys);
+        if let Ok(Value::Object(map)) = &json_result {
+            notify_line(map, output_config, state);
+        }
+        // Lines that aren't valid JSON are written but not counted
+        let is_json_line = json_result.is_ok();
+
+        // single line JSON format
+        if indent < 1 {
+            match json_result {
+                Ok(value) => wln!(writer, "{}", value),
+                Err(raw_error) => {
+                    let column: usize = raw_error.column();
+                    let kind = Kind::from(raw_error);
+                    let error = Error::new(kind, lossy_line(trimmed), line_no, Some(column));
+                    handle_error(writer, error, output_config, state);
+                }
+            }
+        // multi-line indented JSON format with custom indentation
+        } else {
+            let text = String::from_utf8_lossy(trimmed);
+            let formatter = PrettyFormatter::from_str(&text).indent(indent);
+            wln!(writer, "{}", formatter.pretty());
+        }
+        is_rendered = is_json_line;
+    // Inspect log format
+    } else if LogFormat::Inspect == *format {
+        let json_result: Result<Map<String, Value>, SerdeError> =
+            duplicate_keys::from_slice(trimmed, output_config.duplicate_keys);
+
+        match json_result {
+            Ok(map) => {
+                if let Err(e) =
+                    check_required_fields(|field| map.contains_key(field), output_config)
+                {
+                    let error = Error::new(Kind::from(e), lossy_line(trimmed), line_no, None);
+                    handle_error(writer, error, output_config, state);
+                    return false;
+                }
+
+                notify_line(&map, output_config, state);
+
+                // Write JSON-0 output if there are missing fields or the line is too
+                // expensive to format
+                if !has_required_fields(&map) || is_oversized_line(trimmed, output_config) {
+                    write_zero_indent_json(writer, trimmed, output_config, line_no, state);
+                } else {
+                    write_inspect_line(writer, map, output_config);
+                }
+                is_rendered = true;
+            }
+            Err(raw_error) => {
+                let column: usize = raw_error.column();
+                let kind = Kind::from(raw_error);
+                let error = Error::new(kind, lossy_line(trimmed), line_no, Some(column));
+                handle_error(writer, error, output_config, state);
+            }
+        }
+    // Custom log format (eg long, short, simple)
+    } else {
+        let json_result = parse_bunyan_line(trimmed, output_config);
+        match json_result {
+            Ok(mut log) => {
+                if let Err(e) = check_required_fields(|field| log.has_field(field), output_config) {
+                    let error = Error::new(Kind::from(e), lossy_line(trimmed), line_no, None);
+                    handle_error(writer, error, output_config, state);
+                    return false;
+                }
 
-    reader.lines().for_each(|raw_line| {
-        match raw_line {
-            Ok(line) => {
-                line_no += 1;
-                let trimmed = line.trim_start().to_string();
-
-                // Don't process empty lines because the output isn't useful to our users
-                if !output_config.is_strict && trimmed.trim_end().is_empty() {
-                    wln!(writer);
-                } else if let LogFormat::Json(indent) = format {
-                    // single line JSON format
-                    if *indent < 1 {
-                        write_zero_indent_json(writer, trimmed, output_config, line_no);
-                    // multi-line indented JSON format with custom indentation
-                    } else {
-                        let formatter = PrettyFormatter::from_str(&trimmed).indent(*indent);
-                        wln!(writer, "{}", formatter.pretty());
+                if let Some(on_line) = state.on_line.as_deref_mut() {
+                    on_line(&log);
+                }
+
+                // The version warning is only emitted once per stream to avoid flooding STDERR
+                if output_config.warn_unknown_version
+                    && !state.has_warned_version
+                    && log.v != Some(0)
+                {
+                    warn_unknown_version(log.v, line_no);
+                    state.has_warned_version = true;
+                }
+
+                let write_log = is_line_written(&log, line, output_config, state);
+
+                if let (true, Some(field)) = (write_log, &output_config.latency_summary) {
+                    state.latencies.extend(latency_value(&log, field));
+                }
+
+                if write_log && output_config.count_only {
+                    // Counted lines don't need to be rendered
+                    is_rendered = true;
+                } else if write_log && LogFormat::Passthrough == *format {
+                    // The line has been validated, so we write it out byte for byte along with
+                    // its line terminator, if it has one
+                    wb!(writer, raw_line);
+                    is_rendered = true;
+                } else if write_log && is_oversized_line(trimmed, output_config) {
+                    // Oversized lines are written as JSON-0 because they are expensive to format
+                    write_zero_indent_json(writer, trimmed, output_config, line_no, state);
+                    is_rendered = true;
+                } else if write_log {
+                    // Lines with an unknown time can't be the origin of relative times
+                    if output_config.relative_time && !log.is_time_unknown {
+                        log.time_origin = match output_config.anchor_time {
+                            Some(anchor_time) => Some(anchor_time),
+                            None => Some(*state.first_time.get_or_insert(log.time)),
+                        };
                     }
-                // Inspect log format
-                } else if LogFormat::Inspect == *format {
-                    let json_result: Result<Map<String, Value>, SerdeError> =
-                        serde_json::from_str(&trimmed);
-
-                    match json_result {
-                        Ok(map) => {
-                            let has_missing_fields = REQUIRED_FIELDS
-                                .iter()
-                                .any(|field| !map.contains_key(*field));
-                            // Write JSON-0 output if there are missing fields
-                            if has_missing_fields {
-                                write_zero_indent_json(writer, trimmed, output_config, line_no);
-                            } else {
-                                write_inspect_line(writer, map);
-                            }
-                        }
-                        Err(raw_error) => {
-                            let column: usize = raw_error.column();
-                            let kind = Kind::from(raw_error);
-                            let error = Error::new(kind, trimmed, line_no, Some(column));
-                            handle_error(writer, &error, output_config);
+
+                    if output_config.divider_on_reqid_change {
+                        let req_id = log
+                            .other
+                            .get(output_config.req_id_field())
+                            .cloned()
+                            .unwrap_or(Value::Null);
+                        if state
+                            .previous_req_id
+                            .as_ref()
+                            .is_some_and(|prev| *prev != req_id)
+                        {
+                            w!(writer, "{}", DIVIDER.bright_blue());
                         }
+                        state.previous_req_id = Some(req_id);
                     }
-                // Custom log format (eg long, short, simple)
-                } else {
-                    let json_result: Result<BunyanLine, SerdeError> =
-                        serde_json::from_str(&trimmed);
-                    match json_result {
-                        Ok(log) => {
-                            let write_log = match output_config.level {
-                                Some(output_level) => output_level <= log.level,
-                                None => true,
-                            } && match &output_config.condition_filter {
-                                Some(condition_filter) => condition_filter.filter(line.as_str()),
-                                None => true,
-                            };
-
-                            if write_log {
-                                let result = format.write_log(writer, log, output_config);
-                                if let Err(e) = result {
-                                    let kind = Kind::from(e);
-                                    let error = Error::new(kind, trimmed, line_no, None);
-                                    handle_error(writer, &error, output_config);
-                                }
-                            }
-                        }
-                        Err(raw_error) => {
-                            let column: usize = raw_error.column();
-                            let kind = Kind::from(raw_error);
-                            let error = Error::new(kind, trimmed, line_no, Some(column));
-                            handle_error(writer, &error, output_config);
+
+                    if output_config.diff_mode {
+                        let fields = log.other.clone();
+                        if let Some(previous) = state.previous_fields.replace(fields) {
+                            log.other
+                                .retain(|key, value| previous.get(key) != Some(value));
                         }
                     }
+
+                    let result = format.write_log(writer, log, output_config);
+                    is_rendered = result.is_ok();
+                    if let Err(e) = result {
+                        let kind = Kind::from(e);
+                        let error = Error::new(kind, lossy_line(trimmed), line_no, None);
+                        handle_error(writer, error, output_config, state);
+                    }
                 }
             }
-            Err(e) => {
-                panic!("{}", e);
+            Err(raw_error) => {
+                let column: usize = raw_error.column();
+                let kind = Kind::from(raw_error);
+                let error = Error::new(kind, lossy_line(trimmed), line_no, Some(column));
+
+                if LogFormat::Passthrough == *format && is_echoed_as_is(output_config) {
+                    // Lines that are only echoed are written byte for byte like valid lines
+                    wb!(writer, raw_line);
+                } else {
+                    handle_error(writer, error, output_config, state);
+                }
             }
         }
-    });
+    }
+
+    is_rendered
 }
 
+/// Returns true if lines that can't be parsed are written to the output as they were read,
+/// without any error being reported or annotated.
+///
+/// # Arguments
+/// * `output_config` - Configuration defining how errors are handled
+///
+fn is_echoed_as_is(output_config: &LoggerOutputConfig) -> bool {
+    !output_config.is_strict
+        && !output_config.fail_fast
+        && !output_config.is_debug
+        && !output_config.annotate_errors
+}
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn auto_color_mode_follows_the_environment() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert!(resolve_color_mode(ColorMode::Auto, env(&[]), true));
+        assert!(!resolve_color_mode(ColorMode::Auto, env(&[]), false));
+        assert!(!resolve_color_mode(
+            ColorMode::Auto,
+            env(&[("NO_COLOR", "1")]),
+            true
+        ));
+        assert!(!resolve_color_mode(
+            ColorMode::Auto,
+            env(&[("CLICOLOR", "0")]),
+            true
+        ));
+        assert!(resolve_color_mode(
+            ColorMode::Auto,
+            env(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]),
+            false
+        ));
+        assert!(resolve_color_mode(
+            ColorMode::Always,
+            env(&[("NO_COLOR", "1")]),
+            false
+        ));
+        assert!(!resolve_color_mode(ColorMode::Never, env(&[]), true));
+    }
+
     #[test]
     fn can_parse_to_known_log_level() {
         let levels = vec![
@@ -393,6 +2541,50 @@ mod tests {
         assert_log_levels_parse(levels);
     }
 
+    #[test]
+    fn can_detect_bunyan_line() {
+        let line = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}"#;
+        assert!(is_bunyan_line(line));
+    }
+
+    #[test]
+    fn plain_json_object_is_not_bunyan_line() {
+        assert!(!is_bunyan_line(
+            r#"{"name":"myservice","msg":"My message"}"#
+        ));
+        assert!(!is_bunyan_line(
+            r#"["v","level","hostname","pid","time","msg"]"#
+        ));
+    }
+
+    #[test]
+    fn non_json_text_is_not_bunyan_line() {
+        assert!(!is_bunyan_line("this is not json"));
+        assert!(!is_bunyan_line(""));
+    }
+
+    #[test]
+    fn can_round_trip_line_to_json() {
+        let line = r#"{"name":"myservice","hostname":"example.com","pid":123,"level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"req_id":"abc","nested":{"a":[1,2]}}"#;
+        let log: BunyanLine = serde_json::from_str(line).expect("Unable to parse line");
+
+        assert_eq!(
+            log.to_json(),
+            serde_json::from_str::<Value>(line).expect("Unable to parse line")
+        );
+    }
+
+    #[test]
+    fn to_json_omits_missing_optional_fields_and_normalizes_time() {
+        let line = r#"{"hostname":"example.com","pid":123,"level":40,"message":"My message","timestamp":"2012-02-08T14:56:52.856-08:00"}"#;
+        let log: BunyanLine = serde_json::from_str(line).expect("Unable to parse line");
+
+        assert_eq!(
+            serde_json::to_string(&log.to_json()).expect("Unable to write json"),
+            r#"{"hostname":"example.com","pid":123,"level":40,"msg":"My message","time":"2012-02-08T22:56:52.856Z"}"#
+        );
+    }
+
     fn assert_log_levels_parse(levels: Vec<LogLevel>) {
         for test_level in levels {
             let level_string = test_level.as_string();
@@ -0,0 +1,93 @@
+use std::io::{Result, Write};
+
+/// Sequence of characters ending each line of output
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// Line feed, as used on Unix-like systems
+    #[default]
+    Lf,
+    /// Carriage return followed by a line feed, as used on Windows
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+}
+
+/// Struct whose purpose is to wrap any instance that implements the `Write` trait in order to
+/// end each line of output with the configured line ending rather than a line feed. Line
+/// feeds that already follow a carriage return are written as is.
+pub struct LineEndingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    line_ending: LineEnding,
+    /// Whether the last byte written is a carriage return
+    is_after_cr: bool,
+}
+
+impl<'a, W: Write> LineEndingWriter<'a, W> {
+    pub fn new(inner: &'a mut W, line_ending: LineEnding) -> LineEndingWriter<'a, W> {
+        LineEndingWriter {
+            inner,
+            line_ending,
+            is_after_cr: false,
+        }
+    }
+}
+
+impl<'a, W: Write> Write for LineEndingWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.line_ending == LineEnding::Lf {
+            self.inner.write_all(buf)?;
+            return Ok(buf.len());
+        }
+
+        for line in buf.split_inclusive(|byte| *byte == b'\n') {
+            match line.strip_suffix(b"\n") {
+                Some(text) => {
+                    let is_crlf = text.last().map_or(self.is_after_cr, |byte| *byte == b'\r');
+                    self.inner.write_all(text)?;
+                    if is_crlf {
+                        self.inner.write_all(b"\n")?;
+                    } else {
+                        self.inner.write_all(self.line_ending.as_bytes())?;
+                    }
+                    self.is_after_cr = false;
+                }
+                None => {
+                    self.inner.write_all(line)?;
+                    if let Some(byte) = line.last() {
+                        self.is_after_cr = *byte == b'\r';
+                    }
+                }
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crlf_is_not_doubled_across_writes() {
+        let mut output: Vec<u8> = Vec::new();
+        let mut writer = LineEndingWriter::new(&mut output, LineEnding::CrLf);
+
+        writer.write_all(b"a\r").unwrap();
+        writer.write_all(b"\nb\n\r\n").unwrap();
+
+        assert_eq!(output, b"a\r\nb\r\n\r\n");
+    }
+}
@@ -0,0 +1,38 @@
+use std::io::{Result, Write};
+
+/// Struct whose purpose is to wrap any instance that implements the `Write` trait in order to
+/// write a prefix (eg the name of the file being read) at the start of each line of output.
+pub struct LinePrefixWriter<'a, W: Write> {
+    inner: &'a mut W,
+    prefix: &'a str,
+    is_line_start: bool,
+}
+
+impl<'a, W: Write> LinePrefixWriter<'a, W> {
+    pub fn new(inner: &'a mut W, prefix: &'a str) -> LinePrefixWriter<'a, W> {
+        LinePrefixWriter {
+            inner,
+            prefix,
+            is_line_start: true,
+        }
+    }
+}
+
+impl<'a, W: Write> Write for LinePrefixWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        for line in buf.split_inclusive(|byte| *byte == b'\n') {
+            if self.is_line_start {
+                self.inner.write_all(self.prefix.as_bytes())?;
+            }
+
+            self.inner.write_all(line)?;
+            self.is_line_start = line.ends_with(b"\n");
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
@@ -32,6 +32,18 @@ macro_rules! wln {
     }};
 }
 
+macro_rules! wb {
+    ($dst:expr, $bytes:expr) => {{
+        match $dst.write_all($bytes) {
+            Ok(_) => {}
+            Err(_) => {
+                // Exit without message because this is likely a SIGPIPE
+                ::std::process::exit(1);
+            }
+        }
+    }};
+}
+
 macro_rules! string_or_value {
     ($val:expr) => {
         if $val.is_string() {
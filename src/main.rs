@@ -3,13 +3,25 @@ extern crate clap;
 extern crate bunyan_view;
 extern crate flate2;
 extern crate pager;
+extern crate terminal_size;
+extern crate zstd;
 
-use bunyan_view::{ConditionFilter, LogFormat, LogLevel, LoggerOutputConfig};
+use bunyan_view::{
+    ColorMode, ConditionFilter, DuplicateKeys, FieldStyle, FollowReader, LineEnding, LogFormat,
+    LogLevel, LoggerOutputConfig, TimeFallback, ViewError,
+};
+use chrono::{DateTime, Utc};
 use clap::{App, AppSettings, Arg, ArgMatches};
+use colored::Color;
 use flate2::read::GzDecoder;
 use pager::Pager;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Read};
+use terminal_size::{terminal_size, Width};
+
+/// Magic number at the start of every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
 
 fn main() {
     let env_var_help = "Environment Variables:
@@ -29,11 +41,41 @@ fn main() {
             .short("d")
             .takes_value(false)
             .required(false))
+        .arg(Arg::with_name("debug-json")
+            .help("Display deserialization errors to STDERR as JSON objects. Implies --debug.")
+            .long("debug-json")
+            .takes_value(false)
+            .required(false))
         .arg(Arg::with_name("strict")
             .help("Suppress all but legal Bunyan JSON log lines. By default non-JSON, and non-Bunyan lines are passed through.")
             .long("strict")
             .takes_value(false)
             .required(false))
+        .arg(Arg::with_name("fail-fast")
+            .help("Stop at the first line that isn't a legal Bunyan JSON log line and exit with an error.")
+            .long("fail-fast")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("raw-error")
+            .help("Append the reason a line couldn't be parsed to passed through lines.")
+            .long("raw-error")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("errors-only")
+            .help("Only show the lines that can't be parsed as bunyan log lines")
+            .long("errors-only")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("keep-blank-lines")
+            .help("Preserve blank lines in the output, even when --strict is set.")
+            .long("keep-blank-lines")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("warn-unknown-version")
+            .help("Warn once on STDERR when a log line has a missing or unrecognized bunyan version.")
+            .long("warn-unknown-version")
+            .takes_value(false)
+            .required(false))
         .arg(Arg::with_name("level")
             .help("Only show messages at or above the specified level.
 You can specify level *names* or the internal numeric values.")
@@ -41,20 +83,70 @@ You can specify level *names* or the internal numeric values.")
             .short("l")
             .takes_value(true)
             .required(false))
+        .arg(Arg::with_name("component-level")
+            .help("Only show messages for the given component at or above the specified level,
+overriding --level for that component. May be specified multiple times.
+E.g.:
+  --component-level db=debug")
+            .long("component-level")
+            .takes_value(true)
+            .value_name("component=level")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("level-label")
+            .help("Display custom levels from the given code up to the next standard level with
+the given label. May be specified multiple times.
+E.g.:
+  --level-label 25=NOTICE")
+            .long("level-label")
+            .takes_value(true)
+            .value_name("code=label")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("level-color")
+            .help("Display the levels from the given code up to the next standard level in the given color.
+May be specified multiple times.
+E.g.:
+  --level-color 45=bright_red")
+            .long("level-color")
+            .takes_value(true)
+            .value_name("code=color")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("exclude-level")
+            .help("Don't show messages at the specified level. May be specified multiple times.
+E.g.:
+  --exclude-level trace --exclude-level debug")
+            .long("exclude-level")
+            .takes_value(true)
+            .value_name("level")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
         .arg(Arg::with_name("condition")
             .help(r#"Run each log message through the condition and only show those that return truish.
 E.g.:
   -c 'this.pid == 123'
   -c 'this.level == DEBUG'
   -c 'this.msg.indexOf("boom") != -1'
+  -c 'tags contains "urgent"'
 "CONDITION" must be (somewhat) legal JS code.
 `this` holds the log record.
+`field contains value` matches arrays having the value and strings having the value as a substring.
 The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.level`.
             "#)
             .long("condition")
             .short("c")
             .takes_value(true)
             .required(false))
+        .arg(Arg::with_name("condition-ignore-case")
+            .help("Compare the strings of the condition and of the log records regardless of their case.")
+            .long("condition-ignore-case")
+            .takes_value(false)
+            .required(false))
         .arg(Arg::with_name("pager")
             .help("Pipe output into `less` (or $PAGER if set), if stdout is a TTY. This overrides $BUNYAN_NO_PAGER.")
             .long("pager")
@@ -75,6 +167,74 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
             .long("no-color")
             .takes_value(false)
             .required(false))
+        .arg(Arg::with_name("component-separator")
+            .help("Character used to separate the name, component and pid in the long output format.")
+            .long("component-separator")
+            .takes_value(true)
+            .value_name("char")
+            .required(false))
+        .arg(Arg::with_name("level-width")
+            .help("Width of the gutter the log level is right aligned in. Defaults to 5.")
+            .long("level-width")
+            .takes_value(true)
+            .value_name("width")
+            .required(false))
+        .arg(Arg::with_name("inline-value-threshold")
+            .help("Display string parameters longer than the specified number of characters below the line rather than inline. Defaults to 50.")
+            .long("inline-value-threshold")
+            .takes_value(true)
+            .value_name("length")
+            .required(false))
+        .arg(Arg::with_name("tab-width")
+            .help("Display tabs in string parameters as the specified number of spaces.")
+            .long("tab-width")
+            .takes_value(true)
+            .value_name("width")
+            .required(false))
+        .arg(Arg::with_name("duplicate-keys")
+            .help("Keep the \"first\" or the \"last\" (the default, like node-bunyan) value of keys
+appearing more than once in a line.")
+            .long("duplicate-keys")
+            .takes_value(true)
+            .value_name("policy")
+            .required(false))
+        .arg(Arg::with_name("line-ending")
+            .help("End lines of output with \"lf\" (the default) or \"crlf\".")
+            .long("line-ending")
+            .takes_value(true)
+            .value_name("style")
+            .required(false))
+        .arg(Arg::with_name("limit")
+            .help("Stop reading the input after displaying the specified number of log lines.")
+            .long("limit")
+            .short("n")
+            .takes_value(true)
+            .value_name("count")
+            .required(false))
+        .arg(Arg::with_name("max-line-bytes")
+            .help("Write lines longer than the specified number of bytes as bunyan JSON rather than formatting them.")
+            .long("max-line-bytes")
+            .takes_value(true)
+            .value_name("bytes")
+            .required(false))
+        .arg(Arg::with_name("default-name")
+            .help("Name displayed for log lines that don't have a name field.")
+            .long("default-name")
+            .takes_value(true)
+            .value_name("name")
+            .required(false))
+        .arg(Arg::with_name("group-by")
+            .help("Read all of the input and display lines grouped by the value of the given field (eg req_id).")
+            .long("group-by")
+            .takes_value(true)
+            .value_name("field")
+            .required(false))
+        .arg(Arg::with_name("merge-window")
+            .help("Display lines ordered by time, buffering up to the given number of lines to reorder lines that are slightly out of order.")
+            .long("merge-window")
+            .takes_value(true)
+            .value_name("lines")
+            .required(false))
         .arg(Arg::with_name("output")
             .help("Specify an output mode/format. One of
   bunyan: 0 indented JSON, bunyan's native format
@@ -83,7 +243,10 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
   json-N: JSON output, N-space indent, e.g. \"json-4\"
   long: (the default) pretty
   short: like \"long\", but more concise
-  simple: level, followed by \"-\" and then the message")
+  simple: level, followed by \"-\" and then the message
+  passthrough: valid bunyan lines reprinted exactly as read
+  gelf: GELF 1.1 JSON for ingestion by Graylog
+  json-array: all lines as a single JSON array, 2-space indent")
             .long("output")
             .short("o")
             .takes_value(true)
@@ -99,12 +262,251 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
             .short("0")
             .takes_value(false)
             .required(false))
+        .arg(Arg::with_name("numeric-level")
+            .help("Display the numeric value of log levels rather than their names")
+            .long("numeric-level")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("multiline-key-on-own-line")
+            .help("Display the key of multiline string parameters on its own line followed by the indented value")
+            .long("multiline-key-on-own-line")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("sort-params")
+            .help("Display the extra parameters of each line sorted by key")
+            .long("sort-params")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("inline-arrays")
+            .help("Display short arrays of strings, numbers and booleans inline with the other parameters")
+            .long("inline-arrays")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("compact")
+            .help("Don't display the request, response and error blocks below each line of the long format")
+            .long("compact")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("reqid-divider")
+            .help("Display a divider between consecutive lines with different req_id values")
+            .long("reqid-divider")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("show-version")
+            .help("Display the bunyan schema version of each line after its header")
+            .long("show-version")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("escape-control-chars")
+            .help("Display control characters of messages and parameters as \\xNN escapes")
+            .long("escape-control-chars")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("pretty-json-bodies")
+            .help("Pretty print request bodies that are JSON encoded in a string")
+            .long("pretty-json-bodies")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("color-by-component")
+            .help("Display the name and component of each line in a color picked from its component")
+            .long("color-by-component")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("slow-response")
+            .help("Highlight the status line of responses taking longer than the specified number of milliseconds.")
+            .long("slow-response")
+            .takes_value(true)
+            .value_name("ms")
+            .required(false))
+        .arg(Arg::with_name("stable-inspect")
+            .help("Write the inspect output with sorted keys and one value per line, for diffing.")
+            .long("stable-inspect")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("pid")
+            .help("Only show messages logged by the process with the specified pid.")
+            .long("pid")
+            .takes_value(true)
+            .value_name("pid")
+            .required(false))
+        .arg(Arg::with_name("count")
+            .help("Only print the number of log lines matching the filters.")
+            .long("count")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("short-trace-ids")
+            .help("Truncate OpenTelemetry trace and span IDs to their first 8 hex digits.")
+            .long("short-trace-ids")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("diff")
+            .help("Only display the extra fields that changed since the previous line.")
+            .long("diff")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("no-time-brackets")
+            .help("Don't wrap the time of the long format in brackets.")
+            .long("no-time-brackets")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("context-field")
+            .help("Display the keys of the specified object field as extra parameters of the line.")
+            .long("context-field")
+            .takes_value(true)
+            .value_name("field")
+            .required(false))
+        .arg(Arg::with_name("time-fallback")
+            .help("How lines without a time are handled: reject (the default), now or unknown.")
+            .long("time-fallback")
+            .takes_value(true)
+            .value_name("fallback")
+            .required(false))
+        .arg(Arg::with_name("collapse-msg-whitespace")
+            .help("Replace each run of whitespace in messages with a single space.")
+            .long("collapse-msg-whitespace")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("errors-inline")
+            .help("Write debug error messages to the output as comments instead of to STDERR.")
+            .long("errors-inline")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("param-key-color")
+            .help("Display the keys of extra parameters in the given color, or dimmed with \"dim\".")
+            .long("param-key-color")
+            .takes_value(true)
+            .value_name("color")
+            .required(false))
+        .arg(Arg::with_name("param-value-color")
+            .help("Display the values of extra parameters in the given color, or dimmed with \"dim\".")
+            .long("param-value-color")
+            .takes_value(true)
+            .value_name("color")
+            .required(false))
+        .arg(Arg::with_name("wrap-header")
+            .help("Display the hostname and src of the long format on their own line when the header is wider than the terminal.")
+            .long("wrap-header")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("latency-summary")
+            .help("Write the 50th, 90th and 99th percentiles of the latencies in the given field after the lines.")
+            .long("latency-summary")
+            .takes_value(true)
+            .value_name("field")
+            .required(false))
+        .arg(Arg::with_name("level-icons")
+            .help("Display an icon before the level of each line.")
+            .long("level-icons")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("unique-msgs")
+            .help("Only display the first line of each distinct message.")
+            .long("unique-msgs")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("compact-objects")
+            .help("Display the objects of the extra parameters as single line JSON when they aren't longer than the given width.")
+            .long("compact-objects")
+            .value_name("width")
+            .takes_value(true)
+            .required(false))
+        .arg(Arg::with_name("wide")
+            .help("Display string parameters inline however long they are, unless they contain line breaks.")
+            .long("wide")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("req-id-field")
+            .help("Display the given field first among the extra parameters as the request id, instead of req_id.")
+            .long("req-id-field")
+            .value_name("field")
+            .takes_value(true)
+            .required(false))
+        .arg(Arg::with_name("expand-query")
+            .help("Display the query parameters of request urls on their own lines below the request")
+            .long("expand-query")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("highlight")
+            .help("Display messages containing the given keyword in the given color. The first
+matching keyword is used. May be specified multiple times.
+E.g.:
+  --highlight timeout=red")
+            .long("highlight")
+            .takes_value(true)
+            .value_name("keyword=color")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("status-phrase")
+            .help("Display the given reason phrase for an HTTP status code. May be specified
+multiple times.
+E.g.:
+  --status-phrase \"499=Client Closed Request\"")
+            .long("status-phrase")
+            .takes_value(true)
+            .value_name("code=phrase")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("require-field")
+            .help("Treat lines that don't contain the given field as invalid. May be specified
+multiple times.")
+            .long("require-field")
+            .takes_value(true)
+            .value_name("field")
+            .multiple(true)
+            .number_of_values(1)
+            .required(false))
+        .arg(Arg::with_name("src-hyperlink")
+            .help("Display the file and line of the src field as a hyperlink to the file")
+            .long("src-hyperlink")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("no-trailing-newline")
+            .help("Don't end the last line of output with a line feed")
+            .long("no-trailing-newline")
+            .takes_value(false)
+            .required(false))
         .arg(Arg::with_name("time-local")
             .help("Display time field in local time, rather than UTC")
             .long("time-local")
             .short("L")
             .takes_value(false)
             .required(false))
+        .arg(Arg::with_name("time-relative")
+            .help("Display time field as the number of seconds since the first displayed line")
+            .long("time-relative")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("time-anchor")
+            .help("Display time field as the number of seconds since the given RFC 3339 time. Implies --time-relative.")
+            .long("time-anchor")
+            .takes_value(true)
+            .value_name("time")
+            .required(false))
+        .arg(Arg::with_name("time-epoch")
+            .help("Display time field as milliseconds since the epoch")
+            .long("time-epoch")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("time-fixed-width")
+            .help("Display the time field in a fixed width column so that the following columns align")
+            .long("time-fixed-width")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("follow")
+            .help("Keep reading the input after reaching its end and display new log lines as they
+are written. Input is never seeked, so named pipes can be followed.")
+            .long("follow")
+            .short("f")
+            .takes_value(false)
+            .required(false))
+        .arg(Arg::with_name("with-filename")
+            .help("Prefix each line of output with the name of the file it was read from")
+            .long("with-filename")
+            .short("H")
+            .takes_value(false)
+            .required(false))
         .arg(Arg::with_name("FILE")
             .help("Sets the input file(s) to use")
             .required(false)
@@ -123,7 +525,338 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
         None => None,
     };
 
-    let condition_filter = matches.value_of("condition").map(ConditionFilter::new);
+    let mut component_levels: HashMap<String, u16> = HashMap::new();
+    if let Some(component_level_strings) = matches.values_of("component-level") {
+        for component_level_string in component_level_strings {
+            let (component, level_string) = match component_level_string.split_once('=') {
+                Some(pair) => pair,
+                None => {
+                    eprintln!(
+                        "error: component level must be in the form component=level: \"{}\"",
+                        component_level_string
+                    );
+                    std::process::exit(1);
+                }
+            };
+
+            match LogLevel::parse(level_string) {
+                Ok(level) => {
+                    component_levels.insert(component.to_string(), level.as_u16());
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", e, level_string);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let mut level_labels: BTreeMap<u16, String> = BTreeMap::new();
+    if let Some(level_label_strings) = matches.values_of("level-label") {
+        for level_label_string in level_label_strings {
+            let (code_string, label) = match level_label_string.split_once('=') {
+                Some(pair) => pair,
+                None => {
+                    eprintln!(
+                        "error: level label must be in the form code=label: \"{}\"",
+                        level_label_string
+                    );
+                    std::process::exit(1);
+                }
+            };
+
+            match code_string.parse::<u16>() {
+                Ok(code) => {
+                    level_labels.insert(code, label.to_string());
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", e, code_string);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let mut exclude_levels: Vec<u16> = Vec::new();
+    if let Some(level_strings) = matches.values_of("exclude-level") {
+        for level_string in level_strings {
+            match LogLevel::parse(level_string) {
+                Ok(level) => exclude_levels.push(level.as_u16()),
+                Err(e) => {
+                    eprintln!("{}: {}", e, level_string);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let mut highlight_rules: Vec<(String, Color)> = Vec::new();
+    if let Some(highlight_strings) = matches.values_of("highlight") {
+        for highlight_string in highlight_strings {
+            // Split on the last '=' because keywords may contain one but colors never do
+            let (keyword, color_string) = match highlight_string.rsplit_once('=') {
+                Some(pair) => pair,
+                None => {
+                    eprintln!(
+                        "error: highlight must be in the form keyword=color: \"{}\"",
+                        highlight_string
+                    );
+                    std::process::exit(1);
+                }
+            };
+
+            match color_string.parse::<Color>() {
+                Ok(color) => highlight_rules.push((keyword.to_string(), color)),
+                Err(_) => {
+                    eprintln!("error: unknown highlight color: \"{}\"", color_string);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let mut level_styles: BTreeMap<u16, FieldStyle> = BTreeMap::new();
+    if let Some(level_color_strings) = matches.values_of("level-color") {
+        for level_color_string in level_color_strings {
+            let (code_string, color_string) = match level_color_string.split_once('=') {
+                Some(pair) => pair,
+                None => {
+                    eprintln!(
+                        "error: level color must be in the form code=color: \"{}\"",
+                        level_color_string
+                    );
+                    std::process::exit(1);
+                }
+            };
+
+            let code = match code_string.parse::<u16>() {
+                Ok(code) => code,
+                Err(e) => {
+                    eprintln!("{}: {}", e, code_string);
+                    std::process::exit(1);
+                }
+            };
+
+            match color_string.parse::<Color>() {
+                Ok(color) => {
+                    level_styles.insert(code, FieldStyle::color(color));
+                }
+                Err(_) => {
+                    eprintln!("error: unknown level color: \"{}\"", color_string);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let parse_param_style = |arg: &str| match matches.value_of(arg) {
+        Some("dim") => Some(FieldStyle::default().dimmed()),
+        Some(color_string) => match color_string.parse::<Color>() {
+            Ok(color) => Some(FieldStyle::color(color)),
+            Err(_) => {
+                eprintln!("error: unknown {}: \"{}\"", arg, color_string);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let param_key_style = parse_param_style("param-key-color");
+    let param_value_style = parse_param_style("param-value-color");
+
+    let mut status_phrases: HashMap<u16, String> = HashMap::new();
+    if let Some(status_phrase_strings) = matches.values_of("status-phrase") {
+        for status_phrase_string in status_phrase_strings {
+            let (code_string, phrase) = match status_phrase_string.split_once('=') {
+                Some(pair) => pair,
+                None => {
+                    eprintln!(
+                        "error: status phrase must be in the form code=phrase: \"{}\"",
+                        status_phrase_string
+                    );
+                    std::process::exit(1);
+                }
+            };
+
+            match code_string.parse::<u16>() {
+                Ok(code) => {
+                    status_phrases.insert(code, phrase.to_string());
+                }
+                Err(e) => {
+                    eprintln!("{}: {}", e, code_string);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    let mut condition_filter = matches.value_of("condition").map(ConditionFilter::new);
+    if matches.is_present("condition-ignore-case") {
+        condition_filter = condition_filter.map(ConditionFilter::case_insensitive);
+    }
+
+    let component_separator = match matches.value_of("component-separator") {
+        Some(separator_string) => {
+            let mut chars = separator_string.chars();
+            match (chars.next(), chars.next()) {
+                (Some(separator), None) => separator,
+                _ => {
+                    eprintln!(
+                        "error: component separator must be a single character: \"{}\"",
+                        separator_string
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => '/',
+    };
+
+    let level_width: usize = match matches.value_of("level-width") {
+        Some(level_width_string) => match level_width_string.parse::<usize>() {
+            Ok(level_width) => level_width,
+            Err(e) => {
+                eprintln!("{}: {}", e, level_width_string);
+                std::process::exit(1);
+            }
+        },
+        None => LoggerOutputConfig::default().level_width,
+    };
+
+    let inline_value_threshold: usize = match matches.value_of("inline-value-threshold") {
+        Some(threshold_string) => match threshold_string.parse::<usize>() {
+            Ok(threshold) => threshold,
+            Err(e) => {
+                eprintln!("{}: {}", e, threshold_string);
+                std::process::exit(1);
+            }
+        },
+        None => LoggerOutputConfig::default().inline_value_threshold,
+    };
+
+    let merge_window: usize = match matches.value_of("merge-window") {
+        Some(merge_window_string) => match merge_window_string.parse::<usize>() {
+            Ok(merge_window) => merge_window,
+            Err(e) => {
+                eprintln!("{}: {}", e, merge_window_string);
+                std::process::exit(1);
+            }
+        },
+        None => LoggerOutputConfig::default().merge_window,
+    };
+
+    let anchor_time: Option<DateTime<Utc>> = match matches.value_of("time-anchor") {
+        Some(anchor_time_string) => match DateTime::parse_from_rfc3339(anchor_time_string) {
+            Ok(anchor_time) => Some(anchor_time.with_timezone(&Utc)),
+            Err(e) => {
+                eprintln!("{}: {}", e, anchor_time_string);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let tab_width: Option<usize> = match matches.value_of("tab-width") {
+        Some(tab_width_string) => match tab_width_string.parse::<usize>() {
+            Ok(tab_width) => Some(tab_width),
+            Err(e) => {
+                eprintln!("{}: {}", e, tab_width_string);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let duplicate_keys = match matches.value_of("duplicate-keys") {
+        Some(policy) => match policy.to_ascii_lowercase().as_ref() {
+            "first" => DuplicateKeys::FirstWins,
+            "last" => DuplicateKeys::LastWins,
+            _policy => {
+                eprintln!("error: unknown duplicate keys policy: \"{}\"", _policy);
+                std::process::exit(1);
+            }
+        },
+        None => DuplicateKeys::LastWins,
+    };
+
+    let time_fallback = match matches.value_of("time-fallback") {
+        Some(fallback) => match fallback.to_ascii_lowercase().as_ref() {
+            "reject" => TimeFallback::Reject,
+            "now" => TimeFallback::Now,
+            "unknown" => TimeFallback::Unknown,
+            _fallback => {
+                eprintln!("error: unknown time fallback: \"{}\"", _fallback);
+                std::process::exit(1);
+            }
+        },
+        None => TimeFallback::Reject,
+    };
+
+    let line_ending = match matches.value_of("line-ending") {
+        Some(style) => match style.to_ascii_lowercase().as_ref() {
+            "lf" => LineEnding::Lf,
+            "crlf" => LineEnding::CrLf,
+            _style => {
+                eprintln!("error: unknown line ending: \"{}\"", _style);
+                std::process::exit(1);
+            }
+        },
+        None => LineEnding::Lf,
+    };
+
+    let limit: Option<usize> = match matches.value_of("limit") {
+        Some(limit_string) => match limit_string.parse::<usize>() {
+            Ok(limit) => Some(limit),
+            Err(e) => {
+                eprintln!("{}: {}", e, limit_string);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let max_line_bytes: Option<usize> = match matches.value_of("max-line-bytes") {
+        Some(max_line_bytes_string) => match max_line_bytes_string.parse::<usize>() {
+            Ok(max_line_bytes) => Some(max_line_bytes),
+            Err(e) => {
+                eprintln!("{}: {}", e, max_line_bytes_string);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let slow_response_ms: Option<u64> = match matches.value_of("slow-response") {
+        Some(slow_response_string) => match slow_response_string.parse::<u64>() {
+            Ok(slow_response_ms) => Some(slow_response_ms),
+            Err(e) => {
+                eprintln!("{}: {}", e, slow_response_string);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let compact_small_objects: Option<usize> = match matches.value_of("compact-objects") {
+        Some(width_string) => match width_string.parse::<usize>() {
+            Ok(width) => Some(width),
+            Err(e) => {
+                eprintln!("{}: {}", e, width_string);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    let pid_filter: Option<usize> = match matches.value_of("pid") {
+        Some(pid_string) => match pid_string.parse::<usize>() {
+            Ok(pid) => Some(pid),
+            Err(e) => {
+                eprintln!("{}: {}", e, pid_string);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
 
     let format = match matches.value_of("output") {
         Some(output_string) => match output_string.to_ascii_lowercase().as_ref() {
@@ -133,6 +866,9 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
             "long" => LogFormat::Long,
             "short" => LogFormat::Short,
             "simple" => LogFormat::Simple,
+            "passthrough" => LogFormat::Passthrough,
+            "gelf" => LogFormat::Gelf,
+            "json-array" => LogFormat::JsonArray(2),
             _mode => {
                 eprintln!("error: unknown output mode: \"{}\"", _mode);
                 std::process::exit(1);
@@ -150,17 +886,95 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
         }
     };
 
-    let output_config = LoggerOutputConfig {
+    let mut output_config = LoggerOutputConfig {
         indent: 4,
         is_strict: matches.is_present("strict"),
-        is_debug: matches.is_present("debug"),
+        is_debug: matches.is_present("debug") || matches.is_present("debug-json"),
         level,
         condition_filter,
         display_local_time: matches.is_present("time-local"),
         format,
+        keep_blank_lines: matches.is_present("keep-blank-lines"),
+        errors_only: matches.is_present("errors-only"),
+        component_separator,
+        warn_unknown_version: matches.is_present("warn-unknown-version"),
+        component_levels,
+        exclude_levels,
+        level_labels,
+        level_styles,
+        annotate_errors: matches.is_present("raw-error"),
+        max_line_bytes,
+        numeric_level: matches.is_present("numeric-level"),
+        multiline_key_on_own_line: matches.is_present("multiline-key-on-own-line"),
+        sort_params: matches.is_present("sort-params"),
+        default_name: matches.value_of("default-name").map(String::from),
+        debug_json: matches.is_present("debug-json"),
+        limit,
+        level_width,
+        inline_value_threshold,
+        tab_width,
+        inline_scalar_arrays: matches.is_present("inline-arrays"),
+        expand_query: matches.is_present("expand-query"),
+        compact: matches.is_present("compact"),
+        divider_on_reqid_change: matches.is_present("reqid-divider"),
+        show_version: matches.is_present("show-version"),
+        escape_control_chars: matches.is_present("escape-control-chars"),
+        duplicate_keys,
+        pretty_json_bodies: matches.is_present("pretty-json-bodies"),
+        fail_fast: matches.is_present("fail-fast"),
+        fixed_time_width: matches.is_present("time-fixed-width"),
+        color_by_component: matches.is_present("color-by-component"),
+        slow_response_ms,
+        stable_inspect: matches.is_present("stable-inspect"),
+        pid_filter,
+        time_as_epoch: matches.is_present("time-epoch"),
+        count_only: matches.is_present("count"),
+        line_ending,
+        truncate_trace_ids: matches.is_present("short-trace-ids"),
+        diff_mode: matches.is_present("diff"),
+        time_brackets: !matches.is_present("no-time-brackets"),
+        context_field: matches.value_of("context-field").map(String::from),
+        time_fallback,
+        collapse_msg_whitespace: matches.is_present("collapse-msg-whitespace"),
+        errors_inline: matches.is_present("errors-inline"),
+        param_key_style,
+        param_value_style,
+        wrap_header: matches.is_present("wrap-header"),
+        // The size is read before the output may be piped to a pager
+        terminal_width: terminal_size().map(|(Width(width), _)| usize::from(width)),
+        latency_summary: matches.value_of("latency-summary").map(String::from),
+        group_by: matches.value_of("group-by").map(String::from),
+        merge_window,
+        relative_time: matches.is_present("time-relative") || anchor_time.is_some(),
+        anchor_time,
+        unique_msgs: matches.is_present("unique-msgs"),
+        compact_small_objects,
+        wide: matches.is_present("wide"),
+        req_id_field: matches.value_of("req-id-field").map(String::from),
+        level_icons: matches
+            .is_present("level-icons")
+            .then(LogLevel::default_icons),
+        highlight_rules,
+        src_as_hyperlink: matches.is_present("src-hyperlink"),
+        status_phrases,
+        no_trailing_newline: matches.is_present("no-trailing-newline"),
+        require_fields: matches
+            .values_of("require-field")
+            .map(|fields| fields.map(String::from).collect())
+            .unwrap_or_default(),
+        ..LoggerOutputConfig::default()
     };
 
-    apply_color_settings(&matches);
+    output_config.color_mode = read_color_mode(&matches);
+    bunyan_view::apply_color_mode(&output_config);
+
+    let follow = matches.is_present("follow");
+    let with_filename = matches.is_present("with-filename");
+
+    if follow && matches.occurrences_of("FILE") > 1 {
+        eprintln!("ERROR: Only a single file can be followed");
+        std::process::exit(1);
+    }
 
     match matches.values_of("FILE") {
         Some(filenames) => {
@@ -177,21 +991,101 @@ The TRACE, DEBUG, ... FATAL values are defined to help with comparing `this.leve
 
                 let file = file_result.unwrap();
 
+                let input: Box<dyn Read> = if follow {
+                    Box::new(FollowReader::new(file))
+                } else {
+                    Box::new(file)
+                };
+
                 // We only enable pager support when a file has been directly specified
                 apply_pager_settings(&matches);
 
-                let reader: Box<dyn BufRead> = if filename.ends_with(".gz") {
-                    Box::new(BufReader::new(GzDecoder::new(BufReader::new(file))))
-                } else {
-                    Box::new(BufReader::new(file))
-                };
+                let reader = decompressing_reader(filename, input, follow);
+
+                if with_filename {
+                    output_config.line_prefix = Some(format!("{}: ", filename));
+                }
 
-                bunyan_view::write_bunyan_output(&mut std::io::stdout(), reader, &output_config);
+                exit_on_error(bunyan_view::write_bunyan_output(
+                    &mut std::io::stdout(),
+                    reader,
+                    &output_config,
+                ));
             }
         }
         None => {
-            let reader = Box::new(BufReader::new(std::io::stdin()));
-            bunyan_view::write_bunyan_output(&mut std::io::stdout(), reader, &output_config);
+            let input: Box<dyn Read> = if follow {
+                Box::new(FollowReader::new(std::io::stdin()))
+            } else {
+                Box::new(std::io::stdin())
+            };
+            let reader = Box::new(BufReader::new(input));
+            exit_on_error(bunyan_view::write_bunyan_output(
+                &mut std::io::stdout(),
+                reader,
+                &output_config,
+            ));
+        }
+    }
+}
+
+/// Exits with an error status when the output stopped at an invalid line in fail fast mode.
+///
+/// # Arguments
+/// * `result` - result of writing the output of an input
+///
+fn exit_on_error(result: Result<(), ViewError>) {
+    let message = match result {
+        Ok(()) => return,
+        Err(ViewError::ParseError {
+            message, line_no, ..
+        })
+        | Err(ViewError::ValidationError { message, line_no }) => {
+            format!("{} on line {}", message, line_no)
+        }
+        // Exit without message because this is likely a SIGPIPE
+        Err(ViewError::IoError(ref e)) if e.kind() == io::ErrorKind::BrokenPipe => {
+            std::process::exit(1)
+        }
+        Err(ViewError::IoError(e)) => e.to_string(),
+    };
+
+    eprintln!("error: {}", message);
+    std::process::exit(1);
+}
+
+/// Wraps the input of a file in a reader decompressing it when it is a gzip or zstd
+/// archive. Gzip archives are detected by their `.gz` extension. Zstd archives are
+/// detected by their `.zst` extension or by the magic number of their first frame. The
+/// magic number isn't sniffed when following a file, because the file may still be empty
+/// and reading ahead would wait for its first line.
+///
+/// # Arguments
+/// * `filename` - name of the file the input is read from
+/// * `input` - Read implementation reading the raw content of the file
+/// * `follow` - whether the file is followed as it grows
+fn decompressing_reader(filename: &str, input: Box<dyn Read>, follow: bool) -> Box<dyn BufRead> {
+    let mut reader = BufReader::new(input);
+
+    if filename.ends_with(".gz") {
+        return Box::new(BufReader::new(GzDecoder::new(reader)));
+    }
+
+    let is_zstd = filename.ends_with(".zst")
+        || (!follow
+            && reader
+                .fill_buf()
+                .is_ok_and(|buffer| buffer.starts_with(&ZSTD_MAGIC)));
+
+    if !is_zstd {
+        return Box::new(reader);
+    }
+
+    match zstd::Decoder::with_buffer(reader) {
+        Ok(decoder) => Box::new(BufReader::new(decoder)),
+        Err(e) => {
+            eprintln!("{}: {}", e, filename);
+            std::process::exit(1);
         }
     }
 }
@@ -214,12 +1108,12 @@ fn apply_pager_settings(matches: &ArgMatches) {
     }
 }
 
-/// Reads the CLI parameters and environment variables set upon execution and selectively
-/// enables or disables color support
+/// Reads the CLI parameters and environment variables set upon execution and returns whether
+/// color support is enabled or disabled
 ///
 /// # Arguments
 /// * `matches` - CLAP flags data structure
-fn apply_color_settings(matches: &ArgMatches) {
+fn read_color_mode(matches: &ArgMatches) -> ColorMode {
     if matches.is_present("color") && matches.is_present("no-color") {
         eprintln!("ERROR: Contradictory color settings: use --no-color OR --color");
         std::process::exit(1);
@@ -227,9 +1121,9 @@ fn apply_color_settings(matches: &ArgMatches) {
 
     // If BUNYAN_NO_COLOR is set, we intentionally ignore the --color setting
     if matches.is_present("no-color") || ::std::env::var_os("BUNYAN_NO_COLOR").is_some() {
-        colored::control::set_override(false);
+        ColorMode::Never
     // Colorized output is the default
     } else {
-        colored::control::set_override(true);
+        ColorMode::Always
     }
 }
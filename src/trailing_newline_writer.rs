@@ -0,0 +1,45 @@
+use std::io::{Result, Write};
+
+/// Struct whose purpose is to wrap any instance that implements the `Write` trait in order to
+/// hold back the line feed ending the data written so far. The held back line feed is only
+/// written once more data follows it, so the output never ends with a line feed.
+pub struct TrailingNewlineWriter<'a, W: Write> {
+    inner: &'a mut W,
+    has_pending_newline: bool,
+}
+
+impl<'a, W: Write> TrailingNewlineWriter<'a, W> {
+    pub fn new(inner: &'a mut W) -> TrailingNewlineWriter<'a, W> {
+        TrailingNewlineWriter {
+            inner,
+            has_pending_newline: false,
+        }
+    }
+}
+
+impl<'a, W: Write> Write for TrailingNewlineWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.has_pending_newline {
+            self.inner.write_all(b"\n")?;
+            self.has_pending_newline = false;
+        }
+
+        match buf.strip_suffix(b"\n") {
+            Some(line) => {
+                self.inner.write_all(line)?;
+                self.has_pending_newline = true;
+            }
+            None => self.inner.write_all(buf)?,
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
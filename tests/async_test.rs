@@ -0,0 +1,128 @@
+#![cfg(feature = "async")]
+
+extern crate bunyan_view;
+#[macro_use]
+extern crate pretty_assertions;
+
+use bunyan_view::{LogFormat, LoggerOutputConfig, ViewError};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncWrite, BufReader};
+
+const SIMPLE_LINE: &str = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}"#;
+
+async fn write_async_output(input: &[u8], output_config: &LoggerOutputConfig) -> String {
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_output_async(&mut writer, BufReader::new(input), output_config)
+        .await
+        .unwrap();
+    String::from_utf8(writer).expect("Couldn't convert bytes")
+}
+
+fn output_config(format: LogFormat) -> LoggerOutputConfig {
+    LoggerOutputConfig {
+        format,
+        ..LoggerOutputConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn async_output_matches_sync_output() {
+    let input = format!("{}\nnot json\n\n{}", SIMPLE_LINE, SIMPLE_LINE);
+
+    for format in [
+        LogFormat::Long,
+        LogFormat::Short,
+        LogFormat::Simple,
+        LogFormat::Json(2),
+        LogFormat::JsonArray(0),
+    ] {
+        let output_config = output_config(format);
+        let mut expected: Vec<u8> = Vec::new();
+        bunyan_view::write_bunyan_output(&mut expected, input.as_bytes(), &output_config).unwrap();
+
+        assert_eq!(
+            write_async_output(input.as_bytes(), &output_config).await,
+            String::from_utf8(expected).unwrap()
+        );
+    }
+}
+
+#[tokio::test]
+async fn async_output_holds_back_trailing_newline() {
+    let input = format!("{}\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    let output_config = LoggerOutputConfig {
+        no_trailing_newline: true,
+        line_prefix: Some("a.log: ".to_string()),
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_async_output(input.as_bytes(), &output_config).await,
+        "a.log: INFO - My message\na.log: INFO - My message"
+    );
+}
+
+#[tokio::test]
+async fn async_output_returns_fail_fast_error() {
+    let input = format!("{}\nnot json\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    let output_config = LoggerOutputConfig {
+        fail_fast: true,
+        ..output_config(LogFormat::Simple)
+    };
+
+    let mut writer: Vec<u8> = Vec::new();
+    let error = bunyan_view::write_bunyan_output_async(
+        &mut writer,
+        BufReader::new(input.as_bytes()),
+        &output_config,
+    )
+    .await
+    .expect_err("malformed line should stop the output");
+
+    match error {
+        ViewError::ParseError { line_no, .. } => assert_eq!(line_no, 2),
+        error => panic!("unexpected error: {:?}", error),
+    }
+    assert_eq!(String::from_utf8(writer).unwrap(), "INFO - My message\n");
+}
+
+/// Writer failing every write, like a closed pipe
+struct BrokenPipeWriter;
+
+impl AsyncWrite for BrokenPipeWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn async_output_returns_write_errors() {
+    for format in [LogFormat::Simple, LogFormat::JsonArray(0)] {
+        let error = bunyan_view::write_bunyan_output_async(
+            &mut BrokenPipeWriter,
+            BufReader::new(SIMPLE_LINE.as_bytes()),
+            &output_config(format),
+        )
+        .await
+        .expect_err("failed write should be returned");
+
+        match error {
+            ViewError::IoError(e) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+const VERSIONLESS_LINE: &str = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z"}"#;
+const VERSION_0_LINE: &str = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}"#;
+
+fn run_bunyan(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_bunyan"))
+        // Options must precede the files, which take all of the trailing arguments
+        .arg("--no-color")
+        .args(args)
+        .env("BUNYAN_NO_PAGER", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Unable to run bunyan");
+
+    child
+        .stdin
+        .take()
+        .expect("stdin not available")
+        .write_all(input.as_bytes())
+        .expect("Unable to write to stdin");
+
+    child.wait_with_output().expect("bunyan did not exit")
+}
+
+#[test]
+fn unknown_version_warning_is_written_once_per_stream() {
+    let input = [VERSIONLESS_LINE, VERSIONLESS_LINE, VERSIONLESS_LINE].join("\n");
+    let output = run_bunyan(&["--warn-unknown-version", "-o", "simple"], &input);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(stderr, "Warning: missing bunyan log version on line 1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "INFO - My message\nINFO - My message\nINFO - My message\n"
+    );
+}
+
+#[test]
+fn unknown_version_warning_is_not_written_for_version_0() {
+    let input = [VERSION_0_LINE, VERSION_0_LINE].join("\n");
+    let output = run_bunyan(&["--warn-unknown-version", "-o", "simple"], &input);
+
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn debug_json_writes_parse_errors_to_stderr_as_json() {
+    let input = [VERSION_0_LINE, r#"{"name":"myservice","pid":}"#].join("\n");
+    let output = run_bunyan(&["--debug-json", "-o", "simple"], &input);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "{\"line\":2,\"column\":27,\"error\":\"expected value\"}\n"
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "INFO - My message\n{\"name\":\"myservice\",\"pid\":}\n"
+    );
+}
+
+#[test]
+fn debug_writes_parse_errors_to_stderr_as_text_by_default() {
+    let output = run_bunyan(
+        &["--debug", "-o", "simple"],
+        r#"{"name":"myservice","pid":}"#,
+    );
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "expected value at on line 1 column: 27\n"
+    );
+}
+
+#[test]
+fn zstd_compressed_file_is_decompressed() {
+    let output = run_bunyan(&["-o", "simple", "tests/corpus/simple.log.zst"], "");
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "INFO - My message\n"
+    );
+}
+
+#[test]
+fn with_filename_prefixes_lines_with_their_source_file() {
+    let output = run_bunyan(
+        &[
+            "-H",
+            "-o",
+            "simple",
+            "tests/corpus/log1.log",
+            "tests/corpus/log2.log",
+        ],
+        "",
+    );
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let sources: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.split_once(": ").unwrap().0)
+        .collect();
+    assert_eq!(
+        sources,
+        [
+            vec!["tests/corpus/log1.log"; 4],
+            vec!["tests/corpus/log2.log"; 5]
+        ]
+        .concat()
+    );
+}
+
+#[test]
+fn fail_fast_exits_with_error_at_first_malformed_line() {
+    let input = format!("{}\nnot json\n{}\n", VERSION_0_LINE, VERSION_0_LINE);
+    let output = run_bunyan(&["--fail-fast", "-o", "simple"], &input);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "INFO - My message\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("on line 2"));
+}
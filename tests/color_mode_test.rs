@@ -0,0 +1,22 @@
+// Colorization is always disabled by the `dumb_terminal` feature
+#![cfg(not(feature = "dumb_terminal"))]
+
+extern crate bunyan_view;
+
+use bunyan_view::{ColorMode, LoggerOutputConfig};
+
+fn color_mode_config(color_mode: ColorMode) -> LoggerOutputConfig {
+    LoggerOutputConfig {
+        color_mode,
+        ..LoggerOutputConfig::default()
+    }
+}
+
+#[test]
+fn always_and_never_ignore_the_environment() {
+    let always = color_mode_config(ColorMode::Always);
+    assert!(bunyan_view::effective_color(&always));
+
+    let never = color_mode_config(ColorMode::Never);
+    assert!(!bunyan_view::effective_color(&never));
+}
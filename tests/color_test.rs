@@ -0,0 +1,300 @@
+// Colorization can't be forced on when the `dumb_terminal` feature disables it
+#![cfg(not(feature = "dumb_terminal"))]
+
+extern crate bunyan_view;
+extern crate colored;
+
+use bunyan_view::{FieldStyle, FieldStyles, LogFormat, LoggerOutputConfig};
+use colored::Color;
+
+/* All tests in this file force colorization on, so they are kept apart from tests that
+ * compare against uncolored output. */
+
+const LINE_WITH_PARAMS: &str = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"plain":"value","req":{"method":"GET","url":"/path","headers":{}},"err":{"message":"boom","name":"Error","stack":"Error: boom","code":"EBOOM"}}"#;
+
+fn write_colored_output(input: &[u8], output_config: &LoggerOutputConfig) -> String {
+    colored::control::set_override(true);
+
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_output(&mut writer, input, output_config).unwrap();
+    String::from_utf8(writer).expect("Couldn't convert bytes")
+}
+
+#[test]
+fn styled_fields_carry_their_color_codes() {
+    let mut field_styles = FieldStyles::new();
+    field_styles.insert("msg", FieldStyle::default().bold());
+    field_styles.insert("req.url", FieldStyle::color(Color::Cyan));
+    field_styles.insert("err.*", FieldStyle::color(Color::Red));
+
+    let output_config = LoggerOutputConfig {
+        field_styles,
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(LINE_WITH_PARAMS.as_bytes(), &output_config);
+
+    assert!(
+        actual.contains("\u{1b}[1mMy message\u{1b}[0m"),
+        "{:?}",
+        actual
+    );
+    assert!(actual.contains("\u{1b}[36m/path\u{1b}[0m"), "{:?}", actual);
+    assert!(
+        actual.contains("\u{1b}[31merr.code=EBOOM\u{1b}[0m"),
+        "{:?}",
+        actual
+    );
+    assert!(
+        actual.contains("\u{1b}[31mError: boom\u{1b}[0m"),
+        "{:?}",
+        actual
+    );
+}
+
+#[test]
+fn unstyled_fields_render_without_color_codes() {
+    let mut field_styles = FieldStyles::new();
+    field_styles.insert("err.*", FieldStyle::color(Color::Red));
+
+    let output_config = LoggerOutputConfig {
+        format: LogFormat::Long,
+        field_styles,
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(LINE_WITH_PARAMS.as_bytes(), &output_config);
+
+    assert!(actual.contains(" (plain=value, "), "{:?}", actual);
+}
+
+#[test]
+fn highlighted_keyword_colors_the_message() {
+    let output_config = LoggerOutputConfig {
+        highlight_rules: vec![
+            ("message".to_string(), Color::Red),
+            ("My".to_string(), Color::Yellow),
+        ],
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(LINE_WITH_PARAMS.as_bytes(), &output_config);
+
+    assert!(
+        actual.contains(": \u{1b}[31mMy message\u{1b}[0m"),
+        "{:?}",
+        actual
+    );
+}
+
+#[test]
+fn unmatched_keyword_leaves_the_message_color_unchanged() {
+    let output_config = LoggerOutputConfig {
+        highlight_rules: vec![("timeout".to_string(), Color::Red)],
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(LINE_WITH_PARAMS.as_bytes(), &output_config);
+
+    assert!(
+        actual.contains(": \u{1b}[36mMy message\u{1b}[0m"),
+        "{:?}",
+        actual
+    );
+    assert!(!actual.contains("\u{1b}[31mMy message"), "{:?}", actual);
+}
+
+#[test]
+fn src_is_written_as_hyperlink_when_requested() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"src":{"file":"/app/server.js","line":42,"func":"handle"}}"#;
+
+    let output_config = LoggerOutputConfig {
+        src_as_hyperlink: true,
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(input.as_bytes(), &output_config);
+
+    assert!(
+        actual.contains("\u{1b}]8;;file:///app/server.js\u{1b}\\"),
+        "{:?}",
+        actual
+    );
+    assert!(actual.contains("\u{1b}]8;;\u{1b}\\"), "{:?}", actual);
+
+    let output_config = LoggerOutputConfig::default();
+    let actual = write_colored_output(input.as_bytes(), &output_config);
+
+    assert!(!actual.contains("\u{1b}]8;;"), "{:?}", actual);
+}
+
+#[test]
+fn components_are_colored_consistently_when_requested() {
+    let line_with_component = |component: &str| {
+        format!(
+            r#"{{"name":"myservice","component":"{}","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}}"#,
+            component
+        )
+    };
+    let input = format!(
+        "{}\n{}\n{}\n",
+        line_with_component("db"),
+        line_with_component("api"),
+        line_with_component("db")
+    );
+
+    let output_config = LoggerOutputConfig {
+        color_by_component: true,
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(input.as_bytes(), &output_config);
+    let lines: Vec<&str> = actual.lines().collect();
+
+    assert!(lines[0].contains("\u{1b}[35mdb\u{1b}[0m"), "{:?}", lines[0]);
+    assert!(
+        lines[1].contains("\u{1b}[94mapi\u{1b}[0m"),
+        "{:?}",
+        lines[1]
+    );
+    assert_eq!(lines[0], lines[2]);
+
+    let output_config = LoggerOutputConfig::default();
+    let actual = write_colored_output(input.as_bytes(), &output_config);
+
+    assert!(actual.contains("myservice/db/123 on"), "{:?}", actual);
+}
+
+#[test]
+fn slow_responses_have_their_status_line_highlighted() {
+    let line_with_duration = |duration: &str| {
+        format!(
+            r#"{{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"res":{{"statusCode":200,"duration":{}}}}}"#,
+            duration
+        )
+    };
+    let output_config = LoggerOutputConfig {
+        slow_response_ms: Some(100),
+        ..LoggerOutputConfig::default()
+    };
+
+    for duration in ["99.5", "100", "\"100\""] {
+        let actual = write_colored_output(line_with_duration(duration).as_bytes(), &output_config);
+        assert!(actual.contains("\u{1b}[36mHTTP/1.1"), "{:?}", actual);
+        assert!(!actual.contains("\u{1b}[1;31m"), "{:?}", actual);
+    }
+
+    for duration in ["100.5", "\"250\""] {
+        let actual = write_colored_output(line_with_duration(duration).as_bytes(), &output_config);
+        assert!(
+            actual.contains("\u{1b}[1;31mHTTP/1.1\u{1b}[0m\u{1b}[1;31m 200 OK"),
+            "{:?}",
+            actual
+        );
+    }
+}
+
+#[test]
+fn trace_context_ids_have_their_own_style() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"traceId":"4bf92f3577b34da6a3ce929d0e0e4736","plain":"value"}"#;
+
+    let output_config = LoggerOutputConfig {
+        truncate_trace_ids: true,
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(input.as_bytes(), &output_config);
+
+    assert!(
+        actual.contains("\u{1b}[35mtraceId=4bf92f35\u{1b}[0m"),
+        "{:?}",
+        actual
+    );
+    assert!(actual.contains(", plain=value)"), "{:?}", actual);
+
+    let mut field_styles = FieldStyles::new();
+    field_styles.insert("traceId", FieldStyle::color(Color::Green));
+    let output_config = LoggerOutputConfig {
+        field_styles,
+        ..output_config
+    };
+    let actual = write_colored_output(input.as_bytes(), &output_config);
+
+    assert!(
+        actual.contains("\u{1b}[32mtraceId=4bf92f35\u{1b}[0m"),
+        "{:?}",
+        actual
+    );
+}
+
+#[test]
+fn param_keys_and_values_have_their_own_styles() {
+    let output_config = LoggerOutputConfig {
+        param_key_style: Some(FieldStyle::default().dimmed()),
+        param_value_style: Some(FieldStyle::color(Color::Green)),
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(LINE_WITH_PARAMS.as_bytes(), &output_config);
+
+    assert!(
+        actual.contains("(\u{1b}[2mplain\u{1b}[0m=\u{1b}[32mvalue\u{1b}[0m, "),
+        "{:?}",
+        actual
+    );
+    assert!(
+        actual.contains("\u{1b}[2merr.code\u{1b}[0m=\u{1b}[32mEBOOM\u{1b}[0m"),
+        "{:?}",
+        actual
+    );
+}
+
+#[test]
+fn header_width_leaves_out_color_codes() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"src":{"file":"/srv/app/lib/handler.js","line":42}}"#;
+
+    // The header is 91 characters wide once its color codes are left out
+    let output_config = LoggerOutputConfig {
+        wrap_header: true,
+        terminal_width: Some(91),
+        ..LoggerOutputConfig::default()
+    };
+    let actual = write_colored_output(input.as_bytes(), &output_config);
+    assert_eq!(actual.lines().count(), 1, "{:?}", actual);
+
+    let output_config = LoggerOutputConfig {
+        terminal_width: Some(90),
+        ..output_config
+    };
+    let actual = write_colored_output(input.as_bytes(), &output_config);
+    assert_eq!(actual.lines().count(), 2, "{:?}", actual);
+}
+
+fn line_at_level(level: u16) -> String {
+    format!(
+        r#"{{"name":"myservice","pid":123,"hostname":"example.com","level":{},"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}}"#,
+        level
+    )
+}
+
+#[test]
+fn custom_levels_are_colored_like_their_band() {
+    let output_config = LoggerOutputConfig::default();
+
+    let actual = write_colored_output(line_at_level(45).as_bytes(), &output_config);
+    assert!(actual.contains("\u{1b}[35mLVL45\u{1b}[0m"), "{:?}", actual);
+
+    let actual = write_colored_output(line_at_level(55).as_bytes(), &output_config);
+    assert!(actual.contains("\u{1b}[31mLVL55\u{1b}[0m"), "{:?}", actual);
+}
+
+#[test]
+fn configured_level_style_covers_its_band() {
+    let output_config = LoggerOutputConfig {
+        level_styles: [(45, FieldStyle::color(Color::BrightRed))]
+            .into_iter()
+            .collect(),
+        ..LoggerOutputConfig::default()
+    };
+
+    let actual = write_colored_output(line_at_level(47).as_bytes(), &output_config);
+    assert!(actual.contains("\u{1b}[91mLVL47\u{1b}[0m"), "{:?}", actual);
+
+    // The configured style stops at the next standard level
+    let actual = write_colored_output(line_at_level(55).as_bytes(), &output_config);
+    assert!(actual.contains("\u{1b}[31mLVL55\u{1b}[0m"), "{:?}", actual);
+    let actual = write_colored_output(line_at_level(42).as_bytes(), &output_config);
+    assert!(actual.contains("\u{1b}[35mLVL42\u{1b}[0m"), "{:?}", actual);
+}
@@ -25,16 +25,11 @@ fn assert_equals_to_file(filename: &str, expected_filename: &str, format: LogFor
     expected_file.read_to_string(&mut expected).expect(msg);
 
     let output_config = LoggerOutputConfig {
-        indent: 4,
-        is_debug: false,
-        is_strict: false,
-        level: None,
-        condition_filter: None,
-        display_local_time: false,
         format,
+        ..LoggerOutputConfig::default()
     };
 
-    bunyan_view::write_bunyan_output(&mut writer, reader, &output_config);
+    bunyan_view::write_bunyan_output(&mut writer, reader, &output_config).unwrap();
     let actual_bytes: Vec<u8> = writer.into_inner();
     let actual = std::str::from_utf8(&actual_bytes).expect("Couldn't convert bytes");
 
@@ -0,0 +1,2862 @@
+extern crate bunyan_view;
+#[macro_use]
+extern crate pretty_assertions;
+
+use bunyan_view::{
+    BunyanLine, ConditionFilter, CustomRenderers, DuplicateKeys, FollowReader, LineEnding,
+    LogFormat, LogLevel, LoggerOutputConfig, TimeFallback, ViewError,
+};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{BufReader, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SIMPLE_LINE: &str = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}"#;
+
+fn output_config(format: LogFormat) -> LoggerOutputConfig {
+    LoggerOutputConfig {
+        format,
+        ..LoggerOutputConfig::default()
+    }
+}
+
+fn write_output(input: &[u8], output_config: &LoggerOutputConfig) -> String {
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_output(&mut writer, input, output_config).unwrap();
+    String::from_utf8(writer).expect("Couldn't convert bytes")
+}
+
+#[test]
+fn byte_input_matches_string_input_for_all_formats() {
+    let formats = vec![
+        LogFormat::Json(0),
+        LogFormat::Json(2),
+        LogFormat::Inspect,
+        LogFormat::Long,
+        LogFormat::Short,
+        LogFormat::Simple,
+    ];
+
+    for format in formats {
+        let output_config = output_config(format);
+        let without_newline = write_output(SIMPLE_LINE.as_bytes(), &output_config);
+        let with_newline = write_output(format!("{}\n", SIMPLE_LINE).as_bytes(), &output_config);
+
+        assert_eq!(without_newline, with_newline);
+    }
+
+    let output_config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n"
+    );
+}
+
+#[test]
+fn line_with_trailing_carriage_return_is_parsed() {
+    let output_config = output_config(LogFormat::Long);
+    let expected = write_output(format!("{}\n", SIMPLE_LINE).as_bytes(), &output_config);
+    let actual = write_output(format!("{}\r\n", SIMPLE_LINE).as_bytes(), &output_config);
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn invalid_utf8_line_is_passed_through() {
+    let output_config = output_config(LogFormat::Long);
+    let actual = write_output(b"not \xff json\n", &output_config);
+
+    assert_eq!(actual, "not \u{fffd} json\n");
+}
+
+#[test]
+fn crlf_line_endings_are_trimmed() {
+    let input = format!("{}\r\nnot json\r\n\r\n{}\r", SIMPLE_LINE, SIMPLE_LINE);
+
+    for format in [LogFormat::Json(0), LogFormat::Long, LogFormat::Simple] {
+        let output_config = output_config(format);
+        let actual = write_output(input.as_bytes(), &output_config);
+
+        assert!(
+            !actual.contains('\r'),
+            "stray carriage return in: {:?}",
+            actual
+        );
+    }
+
+    let output_config = output_config(LogFormat::Simple);
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - My message\nnot json\n\nINFO - My message\n"
+    );
+}
+
+#[test]
+fn strict_mode_keeps_blank_lines_when_requested() {
+    let input = format!("{}\n\nnot json\n  \n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    let output_config = LoggerOutputConfig {
+        is_strict: true,
+        keep_blank_lines: true,
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - My message\n\n\nINFO - My message\n"
+    );
+}
+
+#[test]
+fn strict_mode_drops_blank_lines_by_default() {
+    let input = format!("{}\n\nnot json\n  \n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    let output_config = LoggerOutputConfig {
+        is_strict: true,
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - My message\nINFO - My message\n"
+    );
+}
+
+#[test]
+fn long_format_uses_custom_component_separator() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","component":"db/pool","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}"#;
+
+    let output_config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/db/pool/123 on example.com: My message\n"
+    );
+
+    let output_config = LoggerOutputConfig {
+        component_separator: ':',
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice:db/pool:123 on example.com: My message\n"
+    );
+}
+
+#[test]
+fn boolean_and_null_params_render_consistently() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"enabled":true,"missing":null,"req":{"method":"GET","url":"/","cached":false,"user":null},"res":{"statusCode":200,"gzip":true,"etag":null},"err":null}"#;
+
+    let output_config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+         (enabled=true, missing=null, req.cached=false, req.user=null, res.gzip=true, res.etag=null, err=null)\n    \
+         GET / HTTP/1.1\n    \
+         --\n    \
+         HTTP/1.1 200 OK\n"
+    );
+}
+
+#[test]
+fn passthrough_format_reprints_valid_lines_verbatim() {
+    let valid = r#"  { "v":0, "time":"2012-02-08T22:56:52.856Z", "msg":"My message","level":30, "hostname":"example.com","pid":123,"name":"myservice" }"#;
+    let input = format!("{}\nnot json\n{{\"plain\":\"json\"}}\n{}\n", valid, valid);
+
+    let output_config = output_config(LogFormat::Passthrough);
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!("{}\nnot json\n{{\"plain\":\"json\"}}\n{}\n", valid, valid)
+    );
+
+    let output_config = LoggerOutputConfig {
+        is_strict: true,
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!("{}\n{}\n", valid, valid)
+    );
+}
+
+#[test]
+fn passthrough_format_keeps_invalid_utf8_and_missing_final_newline() {
+    let mut input: Vec<u8> = Vec::new();
+    input.extend_from_slice(SIMPLE_LINE.as_bytes());
+    input.extend_from_slice(b"\nnot \xff json\n");
+    input.extend_from_slice(SIMPLE_LINE.as_bytes());
+
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_output(
+        &mut writer,
+        input.as_slice(),
+        &output_config(LogFormat::Passthrough),
+    )
+    .unwrap();
+
+    assert_eq!(writer, input);
+}
+
+#[test]
+fn component_levels_override_global_level() {
+    let line = |component: &str, level: u16, msg: &str| {
+        format!(
+            r#"{{"name":"myservice","pid":123,"hostname":"example.com","component":"{}","level":{},"msg":"{}","time":"2012-02-08T22:56:52.856Z","v":0}}"#,
+            component, level, msg
+        )
+    };
+    let input = [
+        line("db", 20, "db debug"),
+        line("db", 10, "db trace"),
+        line("http", 20, "http debug"),
+        line("http", 30, "http info"),
+        r#"{"name":"myservice","pid":123,"hostname":"example.com","level":20,"msg":"no component debug","time":"2012-02-08T22:56:52.856Z","v":0}"#.to_string(),
+    ]
+    .join("\n");
+
+    let mut component_levels = HashMap::new();
+    component_levels.insert("db".to_string(), 20);
+
+    let output_config = LoggerOutputConfig {
+        level: Some(30),
+        component_levels,
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "DEBUG - db debug\nINFO - http info\n"
+    );
+}
+
+#[test]
+fn excluded_levels_are_dropped() {
+    let input = [10, 20, 25, 30, 60]
+        .iter()
+        .map(|level| SIMPLE_LINE.replace(r#""level":30"#, &format!(r#""level":{}"#, level)))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let output_config = LoggerOutputConfig {
+        exclude_levels: vec![10, 20],
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "LVL25 - My message\nINFO - My message\nFATAL - My message\n"
+    );
+
+    let output_config = LoggerOutputConfig {
+        level: Some(30),
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - My message\nFATAL - My message\n"
+    );
+}
+
+#[test]
+fn gelf_format_maps_levels_to_syslog_severity() {
+    let levels = [
+        (10, 7),
+        (20, 7),
+        (30, 6),
+        (40, 4),
+        (50, 3),
+        (60, 2),
+        (35, 6),
+    ];
+
+    for (level, severity) in levels.iter() {
+        let input = format!(
+            r#"{{"name":"myservice","pid":123,"hostname":"example.com","level":{},"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}}"#,
+            level
+        );
+        let output_config = output_config(LogFormat::Gelf);
+
+        assert_eq!(
+            write_output(input.as_bytes(), &output_config),
+            format!(
+                "{{\"version\":\"1.1\",\"host\":\"example.com\",\"short_message\":\"My message\",\"timestamp\":1328741812.856,\"level\":{},\"_name\":\"myservice\",\"_pid\":123}}\n",
+                severity
+            )
+        );
+    }
+}
+
+#[test]
+fn gelf_format_prefixes_additional_fields() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","component":"db","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"id":"reserved","req_id":"abc","latency":12,"cached":true,"user":null,"req":{"method":"GET"}}"#;
+    let output_config = output_config(LogFormat::Gelf);
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "{\"version\":\"1.1\",\"host\":\"example.com\",\"short_message\":\"My message\",\"timestamp\":1328741812.856,\"level\":6,\"_name\":\"myservice\",\"_pid\":123,\"_component\":\"db\",\"_req_id\":\"abc\",\"_latency\":12,\"_cached\":\"true\",\"_req\":\"{\\\"method\\\":\\\"GET\\\"}\"}\n"
+    );
+}
+
+const NESTED_LINE: &str = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"nested":{"outer":{"inner":1}}}"#;
+
+#[test]
+fn nested_objects_use_default_indent_step() {
+    let output_config = output_config(LogFormat::Long);
+
+    assert_eq!(
+        write_output(NESTED_LINE.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    nested: {\n",
+            "      \"outer\": {\n",
+            "        \"inner\": 1\n",
+            "      }\n",
+            "    }\n",
+        )
+    );
+}
+
+#[test]
+fn nested_objects_use_configured_indent_step() {
+    let output_config = LoggerOutputConfig {
+        object_indent_step: 4,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(NESTED_LINE.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    nested: {\n",
+            "        \"outer\": {\n",
+            "            \"inner\": 1\n",
+            "        }\n",
+            "    }\n",
+        )
+    );
+
+    let output_config = LoggerOutputConfig {
+        object_indent_step: 2,
+        object_indent_guide: Some('|'),
+        ..output_config
+    };
+
+    assert_eq!(
+        write_output(NESTED_LINE.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    nested: {\n",
+            "    | \"outer\": {\n",
+            "    | | \"inner\": 1\n",
+            "    | }\n",
+            "    }\n",
+        )
+    );
+}
+
+#[test]
+fn parse_errors_are_annotated_when_requested() {
+    let output_config = LoggerOutputConfig {
+        annotate_errors: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(b"{\"name\":\"myservice\"}\nnot json\n", &output_config),
+        "{\"name\":\"myservice\"} # parse error: missing field `hostname`\n\
+         not json # parse error: expected ident\n"
+    );
+}
+
+#[test]
+fn parse_errors_are_not_annotated_by_default() {
+    let output_config = output_config(LogFormat::Long);
+
+    assert_eq!(
+        write_output(b"{\"name\":\"myservice\"}\nnot json\n", &output_config),
+        "{\"name\":\"myservice\"}\nnot json\n"
+    );
+}
+
+#[test]
+fn time_is_read_from_timestamp_aliases() {
+    let output_config = output_config(LogFormat::Long);
+    let expected = "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n";
+
+    for alias in ["timestamp", "@timestamp"] {
+        let input = format!(
+            r#"{{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","{}":"2012-02-08T22:56:52.856Z","v":0}}"#,
+            alias
+        );
+
+        assert_eq!(write_output(input.as_bytes(), &output_config), expected);
+    }
+}
+
+#[test]
+fn time_takes_precedence_over_timestamp_aliases() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","@timestamp":"2012-02-08T22:56:53.000Z","v":0}"#;
+    let output_config = output_config(LogFormat::Long);
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message (@timestamp=2012-02-08T22:56:53.000Z)\n"
+    );
+}
+
+#[test]
+fn msg_is_read_from_message_aliases() {
+    let output_config = output_config(LogFormat::Long);
+    let expected = "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n";
+
+    for alias in ["message", "log"] {
+        let input = SIMPLE_LINE.replace(r#""msg":"#, &format!(r#""{}":"#, alias));
+
+        assert_eq!(write_output(input.as_bytes(), &output_config), expected);
+    }
+}
+
+#[test]
+fn msg_takes_precedence_over_message_aliases() {
+    let input = SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"message":"Other message""#);
+    let output_config = output_config(LogFormat::Long);
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message (message=\"Other message\")\n"
+    );
+}
+
+#[test]
+fn lines_over_max_line_bytes_are_written_as_json() {
+    let input = r#"{ "name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0 }"#;
+
+    let output_config = LoggerOutputConfig {
+        max_line_bytes: Some(input.len() - 1),
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!("{}\n", SIMPLE_LINE)
+    );
+
+    let output_config = LoggerOutputConfig {
+        max_line_bytes: Some(input.len()),
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n"
+    );
+}
+
+#[test]
+fn lines_over_max_line_bytes_are_filtered() {
+    let input = format!(
+        "{}\n{}\n",
+        SIMPLE_LINE,
+        SIMPLE_LINE.replace("\"level\":30", "\"level\":50")
+    );
+
+    let output_config = LoggerOutputConfig {
+        max_line_bytes: Some(1),
+        level: Some(50),
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!("{}\n", SIMPLE_LINE.replace("\"level\":30", "\"level\":50"))
+    );
+}
+
+#[test]
+fn custom_renderers_replace_default_rendering() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"traceId":"4bf92f3577b34da6a3ce929d0e0e4736","spanId":"00f067aa0ba902b7"}"#;
+
+    let mut custom_renderers = CustomRenderers::new();
+    custom_renderers.insert("traceId", |value, writer| {
+        let trace_id = value.as_str().unwrap_or_default();
+        write!(writer, "{}…", &trace_id[..8])
+    });
+
+    let output_config = LoggerOutputConfig {
+        custom_renderers,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message (traceId=4bf92f35…, spanId=00f067aa0ba902b7)\n"
+    );
+}
+
+#[test]
+fn numeric_levels_are_displayed_when_requested() {
+    let line = |level: u16| {
+        format!(
+            r#"{{"name":"myservice","pid":123,"hostname":"example.com","level":{},"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0}}"#,
+            level
+        )
+    };
+    let input = [line(40), line(42)].join("\n");
+
+    let output_config = LoggerOutputConfig {
+        numeric_level: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]    40: myservice/123 on example.com: My message\n\
+         [2012-02-08T22:56:52.856Z]    42: myservice/123 on example.com: My message\n"
+    );
+
+    let output_config = LoggerOutputConfig {
+        format: LogFormat::Short,
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "22:56:52.856Z    40 myservice: My message\n22:56:52.856Z    42 myservice: My message\n"
+    );
+
+    let output_config = LoggerOutputConfig {
+        format: LogFormat::Simple,
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "40 - My message\n42 - My message\n"
+    );
+}
+
+fn req_line(req_id: Option<&str>, msg: &str) -> String {
+    let line = SIMPLE_LINE.replace("My message", msg);
+    match req_id {
+        Some(req_id) => line.replace(r#","v":0"#, &format!(r#","req_id":"{}","v":0"#, req_id)),
+        None => line,
+    }
+}
+
+#[test]
+fn group_by_writes_interleaved_lines_under_banners() {
+    let input = [
+        req_line(Some("a"), "a1"),
+        req_line(Some("b"), "b1"),
+        req_line(None, "none1"),
+        req_line(Some("a"), "a2"),
+        "not json".to_string(),
+        req_line(Some("b"), "b2"),
+    ]
+    .join("\n");
+    let output_config = LoggerOutputConfig {
+        group_by: Some("req_id".to_string()),
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "=== req_id=a ===\n",
+            "INFO - a1\n",
+            "INFO - a2\n",
+            "=== req_id=b ===\n",
+            "INFO - b1\n",
+            "INFO - b2\n",
+            "=== ungrouped ===\n",
+            "INFO - none1\n",
+            "not json\n",
+        )
+    );
+}
+
+#[test]
+fn group_by_omits_ungrouped_banner_when_all_lines_have_field() {
+    let input = [req_line(Some("a"), "a1"), req_line(Some("a"), "a2")].join("\n");
+    let output_config = LoggerOutputConfig {
+        group_by: Some("req_id".to_string()),
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "=== req_id=a ===\nINFO - a1\nINFO - a2\n"
+    );
+}
+
+#[test]
+fn group_by_resolves_duplicate_keys_with_the_policy() {
+    let input =
+        req_line(Some("a"), "a1").replace("\"req_id\":\"a\"", "\"req_id\":\"a\",\"req_id\":\"b\"");
+    let output_config = LoggerOutputConfig {
+        group_by: Some("req_id".to_string()),
+        duplicate_keys: DuplicateKeys::FirstWins,
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "=== req_id=a ===\nINFO - a1\n"
+    );
+}
+
+/// Reader that fails the test if it is read from
+struct UnreachableReader;
+
+impl Read for UnreachableReader {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        panic!("input was read after the limit was reached");
+    }
+}
+
+#[test]
+fn limit_stops_reading_after_rendering_lines() {
+    let input = format!(
+        "not json\n{}\n{}\n",
+        SIMPLE_LINE,
+        SIMPLE_LINE.replace("My message", "Second")
+    );
+    let reader = BufReader::new(input.as_bytes().chain(UnreachableReader));
+    let output_config = LoggerOutputConfig {
+        limit: Some(2),
+        ..output_config(LogFormat::Simple)
+    };
+    let mut output: Vec<u8> = Vec::new();
+
+    bunyan_view::write_bunyan_output(&mut output, reader, &output_config).unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).expect("Couldn't convert bytes"),
+        "not json\nINFO - My message\nINFO - Second\n"
+    );
+}
+
+#[test]
+fn limit_does_not_count_filtered_lines() {
+    let debug_line = SIMPLE_LINE.replace(r#""level":30"#, r#""level":20"#);
+    let input = [debug_line.as_str(), SIMPLE_LINE, SIMPLE_LINE, SIMPLE_LINE].join("\n");
+    let output_config = LoggerOutputConfig {
+        level: Some(30),
+        limit: Some(2),
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - My message\nINFO - My message\n"
+    );
+}
+
+/// Non-seekable in-memory stream that reports the end of input whenever it is empty
+#[derive(Clone, Default)]
+struct MemoryPipe {
+    buffer: Arc<Mutex<VecDeque<u8>>>,
+}
+
+impl Read for MemoryPipe {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.buffer.lock().unwrap().read(buf)
+    }
+}
+
+impl Write for MemoryPipe {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn wait_for_output(output: &MemoryPipe, expected: &str) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    loop {
+        let actual: Vec<u8> = output.buffer.lock().unwrap().iter().copied().collect();
+        let actual = String::from_utf8(actual).expect("Couldn't convert bytes");
+
+        if actual == expected {
+            return;
+        }
+
+        assert!(
+            Instant::now() < deadline,
+            "timed out with output: {:?}",
+            actual
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn follow_reader_reads_data_appended_to_pipe() {
+    let mut input = MemoryPipe::default();
+    let output = MemoryPipe::default();
+    writeln!(input, "{}", SIMPLE_LINE).unwrap();
+
+    let reader_input = input.clone();
+    let mut writer = output.clone();
+    thread::spawn(move || {
+        let reader = FollowReader::with_poll_interval(reader_input, Duration::from_millis(10));
+        let output_config = output_config(LogFormat::Simple);
+        bunyan_view::write_bunyan_output(&mut writer, BufReader::new(reader), &output_config)
+            .unwrap();
+    });
+
+    wait_for_output(&output, "INFO - My message\n");
+
+    // The pipe is now empty, so the reader has reached the end of input and is polling
+    write!(input, "{}", SIMPLE_LINE.replace("My message", "Appended")).unwrap();
+    input.write_all(b"\n").unwrap();
+
+    wait_for_output(&output, "INFO - My message\nINFO - Appended\n");
+}
+
+const MULTILINE_PARAM_LINE: &str = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"description":"one\ntwo\nthree"}"#;
+
+#[test]
+fn multiline_param_value_follows_key_by_default() {
+    let output_config = output_config(LogFormat::Long);
+
+    assert_eq!(
+        write_output(MULTILINE_PARAM_LINE.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    description: one\n",
+            "    two\n",
+            "    three\n",
+        )
+    );
+}
+
+#[test]
+fn multiline_param_key_is_written_on_own_line_when_requested() {
+    let output_config = LoggerOutputConfig {
+        multiline_key_on_own_line: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(MULTILINE_PARAM_LINE.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    description:\n",
+            "      one\n",
+            "      two\n",
+            "      three\n",
+        )
+    );
+}
+
+#[test]
+fn sort_params_writes_params_in_key_order() {
+    let output_config = LoggerOutputConfig {
+        sort_params: true,
+        ..output_config(LogFormat::Long)
+    };
+    let shuffled = [
+        r#""zeta":1,"alpha":2,"mu":3"#,
+        r#""mu":3,"zeta":1,"alpha":2"#,
+    ];
+
+    for params in shuffled {
+        let line = SIMPLE_LINE.replace(r#""v":0"#, &format!(r#""v":0,{}"#, params));
+
+        assert_eq!(
+            write_output(line.as_bytes(), &output_config),
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message (alpha=2, mu=3, zeta=1)\n"
+        );
+    }
+}
+
+#[test]
+fn default_name_is_used_for_nameless_lines() {
+    let line = SIMPLE_LINE.replace(r#""name":"myservice","#, "");
+    let output_config = LoggerOutputConfig {
+        default_name: Some("fallback".to_string()),
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(line.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: fallback/123 on example.com: My message\n"
+    );
+}
+
+#[test]
+fn default_name_does_not_replace_logged_name() {
+    let output_config = LoggerOutputConfig {
+        default_name: Some("fallback".to_string()),
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n"
+    );
+}
+
+#[test]
+fn level_width_sets_level_gutter_in_long_format() {
+    let output_config = LoggerOutputConfig {
+        level_width: 7,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]    INFO: myservice/123 on example.com: My message\n"
+    );
+}
+
+#[test]
+fn level_width_sets_level_gutter_in_short_format() {
+    let output_config = LoggerOutputConfig {
+        level_width: 0,
+        ..output_config(LogFormat::Short)
+    };
+
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &output_config),
+        "22:56:52.856Z INFO myservice: My message\n"
+    );
+}
+
+#[test]
+fn level_width_aligns_numeric_levels() {
+    let output_config = LoggerOutputConfig {
+        level_width: 3,
+        numeric_level: true,
+        ..output_config(LogFormat::Short)
+    };
+
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &output_config),
+        "22:56:52.856Z  30 myservice: My message\n"
+    );
+}
+
+fn req_url_line(url: &str) -> String {
+    SIMPLE_LINE.replace(
+        r#""v":0"#,
+        &format!(r#""v":0,"req":{{"method":"GET","url":"{}"}}"#, url),
+    )
+}
+
+#[test]
+fn expand_query_writes_query_params_below_request() {
+    let output_config = LoggerOutputConfig {
+        expand_query: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(req_url_line("/path?a=1&b=two&c").as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    GET /path HTTP/1.1\n",
+            "      a=1\n",
+            "      b=two\n",
+            "      c\n",
+        )
+    );
+}
+
+#[test]
+fn expand_query_leaves_url_without_query_unchanged() {
+    let output_config = LoggerOutputConfig {
+        expand_query: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(req_url_line("/path").as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    GET /path HTTP/1.1\n",
+        )
+    );
+}
+
+#[test]
+fn line_missing_required_field_is_treated_as_error() {
+    let with_env = SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"service":"api","env":"prod""#);
+    let without_env = SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"service":"api""#);
+    let input = format!("{}\n{}\n", with_env, without_env);
+
+    let output_config = LoggerOutputConfig {
+        require_fields: vec!["service".to_string(), "env".to_string()],
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!("INFO - My message\n{}\n", without_env)
+    );
+
+    let output_config = LoggerOutputConfig {
+        is_strict: true,
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - My message\n"
+    );
+}
+
+#[test]
+fn missing_required_field_is_annotated() {
+    let output_config = LoggerOutputConfig {
+        require_fields: vec!["env".to_string()],
+        annotate_errors: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &output_config),
+        format!(
+            "{} # parse error: missing required field `env`\n",
+            SIMPLE_LINE
+        )
+    );
+}
+
+#[test]
+fn relative_time_displays_offsets_from_first_line() {
+    let input = [
+        "2012-02-08T22:56:52.856Z",
+        "2012-02-08T22:56:54.090Z",
+        "2012-02-08T22:56:52.356Z",
+    ]
+    .iter()
+    .map(|time| SIMPLE_LINE.replace("2012-02-08T22:56:52.856Z", time))
+    .collect::<Vec<String>>()
+    .join("\n");
+
+    let config = LoggerOutputConfig {
+        relative_time: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[+0.000]  INFO: myservice/123 on example.com: My message\n",
+            "[+1.234]  INFO: myservice/123 on example.com: My message\n",
+            "[-0.500]  INFO: myservice/123 on example.com: My message\n",
+        )
+    );
+
+    let config = LoggerOutputConfig {
+        relative_time: true,
+        ..output_config(LogFormat::Short)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "+0.000  INFO myservice: My message\n",
+            "+1.234  INFO myservice: My message\n",
+            "-0.500  INFO myservice: My message\n",
+        )
+    );
+}
+
+#[test]
+fn json_array_writes_all_lines_as_one_indented_array() {
+    let input = format!("{}\n\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    let element = concat!(
+        "  {\n",
+        "    \"name\": \"myservice\",\n",
+        "    \"pid\": 123,\n",
+        "    \"hostname\": \"example.com\",\n",
+        "    \"level\": 30,\n",
+        "    \"msg\": \"My message\",\n",
+        "    \"time\": \"2012-02-08T22:56:52.856Z\",\n",
+        "    \"v\": 0\n",
+        "  }",
+    );
+
+    let actual = write_output(input.as_bytes(), &output_config(LogFormat::JsonArray(2)));
+    assert_eq!(actual, format!("[\n{},\n{}\n]\n", element, element));
+
+    let parsed: serde_json::Value = serde_json::from_str(&actual).expect("Invalid JSON array");
+    assert_eq!(parsed.as_array().map(Vec::len), Some(2));
+}
+
+#[test]
+fn json_array_without_indent_is_written_on_one_line() {
+    let input = format!("{}\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config(LogFormat::JsonArray(0))),
+        format!("[{},{}]\n", SIMPLE_LINE, SIMPLE_LINE)
+    );
+}
+
+#[test]
+fn json_array_only_contains_the_filtered_lines() {
+    let warning = SIMPLE_LINE.replace(r#""level":30"#, r#""level":40"#);
+    let input = format!("{}\n{}\n{{\"plain\":\"json\"}}\n", SIMPLE_LINE, warning);
+    let output_config = LoggerOutputConfig {
+        level: Some(40),
+        ..output_config(LogFormat::JsonArray(0))
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!("[{}]\n", warning)
+    );
+}
+
+#[test]
+fn src_hyperlink_degrades_to_plain_text_without_colors() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"src":{"file":"/app/server.js","line":42}"#,
+    );
+    let output_config = LoggerOutputConfig {
+        src_as_hyperlink: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com (/app/server.js:42): My message\n"
+    );
+}
+
+#[test]
+fn inline_value_threshold_controls_where_long_strings_are_written() {
+    let value = "x".repeat(100);
+    let input = SIMPLE_LINE.replace(r#""v":0"#, &format!(r#""v":0,"long":"{}""#, value));
+
+    let output_config = output_config(LogFormat::Long);
+    assert_eq!(output_config.inline_value_threshold, 50);
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n    long: {}\n",
+            value
+        )
+    );
+
+    let output_config = LoggerOutputConfig {
+        inline_value_threshold: 120,
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message (long={})\n",
+            value
+        )
+    );
+}
+
+#[test]
+fn errors_only_writes_only_malformed_lines() {
+    let missing_field = r#"{"name":"myservice","msg":"My message"}"#;
+    let input = format!(
+        "{}\nnot json\n\n{}\n{}\n",
+        SIMPLE_LINE, missing_field, SIMPLE_LINE
+    );
+
+    let output_config = LoggerOutputConfig {
+        errors_only: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!("not json\n{}\n", missing_field)
+    );
+
+    let output_config = LoggerOutputConfig {
+        annotate_errors: true,
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!(
+            "not json # parse error: expected ident\n{} # parse error: missing field `hostname`\n",
+            missing_field
+        )
+    );
+}
+
+fn res_status_line(status_code: u16) -> String {
+    SIMPLE_LINE.replace(
+        r#""v":0"#,
+        &format!(r#""v":0,"res":{{"statusCode":{}}}"#, status_code),
+    )
+}
+
+#[test]
+fn status_phrases_override_standard_reason_phrases() {
+    let mut status_phrases = HashMap::new();
+    status_phrases.insert(499, "Client Closed Request".to_string());
+
+    let output_config = LoggerOutputConfig {
+        status_phrases,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(res_status_line(499).as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    HTTP/1.1 499 Client Closed Request\n",
+        )
+    );
+    assert_eq!(
+        write_output(res_status_line(404).as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    HTTP/1.1 404 Not Found\n",
+        )
+    );
+}
+
+#[test]
+fn level_labels_name_ranges_of_custom_levels() {
+    let input = [25, 27, 35]
+        .iter()
+        .map(|level| SIMPLE_LINE.replace(r#""level":30"#, &format!(r#""level":{}"#, level)))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let mut level_labels = BTreeMap::new();
+    level_labels.insert(25, "NOTICE".to_string());
+
+    let output_config = LoggerOutputConfig {
+        level_labels,
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "NOTICE - My message\nNOTICE - My message\nLVL35 - My message\n"
+    );
+
+    let output_config = LoggerOutputConfig {
+        format: LogFormat::Short,
+        level_width: 6,
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "22:56:52.856Z NOTICE myservice: My message\n",
+            "22:56:52.856Z NOTICE myservice: My message\n",
+            "22:56:52.856Z  LVL35 myservice: My message\n",
+        )
+    );
+}
+
+fn lines_at_seconds(seconds: &[u8]) -> String {
+    seconds
+        .iter()
+        .map(|second| {
+            SIMPLE_LINE
+                .replace("22:56:52.856Z", &format!("22:56:{:02}.000Z", second))
+                .replace("My message", &format!("at {}", second))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[test]
+fn merge_window_reorders_transposed_lines() {
+    let output_config = LoggerOutputConfig {
+        merge_window: 2,
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(lines_at_seconds(&[1, 3, 2, 4]).as_bytes(), &output_config),
+        "INFO - at 1\nINFO - at 2\nINFO - at 3\nINFO - at 4\n"
+    );
+}
+
+#[test]
+fn merge_window_leaves_lines_beyond_window_out_of_order() {
+    let output_config = LoggerOutputConfig {
+        merge_window: 1,
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(lines_at_seconds(&[2, 3, 4, 1]).as_bytes(), &output_config),
+        "INFO - at 2\nINFO - at 3\nINFO - at 1\nINFO - at 4\n"
+    );
+}
+
+fn res_header_line(header: &str) -> String {
+    SIMPLE_LINE.replace(
+        r#""v":0"#,
+        &format!(
+            r#""v":0,"res":{{"statusCode":200,"header":{}}}"#,
+            serde_json::Value::from(header)
+        ),
+    )
+}
+
+#[test]
+fn truncated_res_header_status_line_is_written() {
+    let output_config = output_config(LogFormat::Long);
+
+    assert_eq!(
+        write_output(res_header_line("HTTP/1").as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    HTTP/1 200 OK\n",
+            "    HTTP/1\n",
+        )
+    );
+}
+
+#[test]
+fn res_header_without_status_line_uses_default_version() {
+    let output_config = output_config(LogFormat::Long);
+
+    assert_eq!(
+        write_output(
+            res_header_line("Foo: bar\r\nContent-Length: 21\r\n\r\n").as_bytes(),
+            &output_config
+        ),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    HTTP/1.1 200 OK\n",
+            "    Foo: bar\n",
+            "    Content-Length: 21\n",
+        )
+    );
+}
+
+#[test]
+fn tab_width_expands_tabs_in_string_params() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"inline":"a\tb","block":"first\n\tsecond""#,
+    );
+
+    let config = LoggerOutputConfig {
+        tab_width: Some(2),
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message (inline=\"a  b\")\n",
+            "    block: first\n",
+            "      second\n",
+        )
+    );
+
+    let config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message (inline=a\tb)\n",
+            "    block: first\n",
+            "    \tsecond\n",
+        )
+    );
+}
+
+#[test]
+fn inline_scalar_arrays_writes_short_scalar_arrays_inline() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"tags":["a","b c",3,true],"nested":[{"a":1}]"#,
+    );
+
+    let config = LoggerOutputConfig {
+        inline_scalar_arrays: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message (tags=[a, \"b c\", 3, true])\n",
+            "    nested: [\n",
+            "      {\n",
+            "        \"a\": 1\n",
+            "      }\n",
+            "    ]\n",
+        )
+    );
+
+    let config = output_config(LogFormat::Long);
+    assert!(write_output(input.as_bytes(), &config).contains("    tags: [\n"));
+}
+
+#[test]
+fn no_trailing_newline_omits_only_the_last_line_feed() {
+    let input = format!("{}\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+
+    let config = LoggerOutputConfig {
+        no_trailing_newline: true,
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "INFO - My message\nINFO - My message"
+    );
+
+    let config = LoggerOutputConfig {
+        no_trailing_newline: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(req_url_line("/path").as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    GET /path HTTP/1.1",
+        )
+    );
+}
+
+#[test]
+fn compact_long_format_skips_detail_blocks() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"req":{"method":"GET","url":"/","cached":false},"res":{"statusCode":200},"err":{"message":"boom","name":"Error","stack":"Error: boom"}}"#;
+    let first_line = "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+                      (req.cached=false)\n";
+
+    let config = LoggerOutputConfig {
+        compact: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(write_output(input.as_bytes(), &config), first_line);
+
+    let config = output_config(LogFormat::Long);
+    let actual = write_output(input.as_bytes(), &config);
+    assert!(actual.starts_with(first_line), "{:?}", actual);
+    assert!(actual.contains("    GET / HTTP/1.1\n"), "{:?}", actual);
+    assert!(actual.contains("    HTTP/1.1 200 OK\n"), "{:?}", actual);
+    assert!(actual.contains("    Error: boom\n"), "{:?}", actual);
+}
+
+#[test]
+fn divider_is_written_between_distinct_req_ids() {
+    let input = [("a", "one"), ("a", "two"), ("b", "three")]
+        .iter()
+        .map(|(req_id, msg)| {
+            SIMPLE_LINE
+                .replace("My message", msg)
+                .replace("\"v\":0", &format!("\"v\":0,\"req_id\":\"{}\"", req_id))
+        })
+        .chain(std::iter::once(SIMPLE_LINE.replace("My message", "four")))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let config = LoggerOutputConfig {
+        divider_on_reqid_change: true,
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "INFO - one\nINFO - two\n--\nINFO - three\n--\nINFO - four\n"
+    );
+
+    let config = output_config(LogFormat::Simple);
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "INFO - one\nINFO - two\nINFO - three\nINFO - four\n"
+    );
+}
+
+#[test]
+fn show_version_appends_schema_version_to_header() {
+    let config = LoggerOutputConfig {
+        show_version: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com (v0): My message\n"
+    );
+
+    let without_version = SIMPLE_LINE.replace(",\"v\":0", "");
+    assert_eq!(
+        write_output(without_version.as_bytes(), &config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n"
+    );
+
+    let config = LoggerOutputConfig {
+        show_version: true,
+        ..output_config(LogFormat::Short)
+    };
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &config),
+        "22:56:52.856Z  INFO myservice (v0): My message\n"
+    );
+}
+
+#[test]
+fn line_callback_sees_parsed_lines_including_filtered_ones() {
+    let input = format!(
+        "{}\nnot json\n{}\n",
+        SIMPLE_LINE,
+        SIMPLE_LINE
+            .replace("\"level\":30", "\"level\":20")
+            .replace("My message", "Debug message")
+    );
+    let output_config = LoggerOutputConfig {
+        level: Some(30),
+        ..output_config(LogFormat::Simple)
+    };
+
+    let mut messages: Vec<String> = Vec::new();
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_output_with(&mut writer, input.as_bytes(), &output_config, |line| {
+        messages.push(line.to_json()["msg"].as_str().unwrap().to_string())
+    })
+    .unwrap();
+
+    assert_eq!(messages, vec!["My message", "Debug message"]);
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "INFO - My message\nnot json\n"
+    );
+}
+
+#[test]
+fn line_callback_is_invoked_for_json_output() {
+    let input = format!("{}\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    let output_config = output_config(LogFormat::Json(0));
+
+    let mut count: usize = 0;
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_output_with(&mut writer, input.as_bytes(), &output_config, |_| {
+        count += 1
+    })
+    .unwrap();
+
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn multi_value_object_headers_are_written_once_per_value() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"res":{"statusCode":200,"headers":{"set-cookie":["a=1","b=2"],"content-type":"text/plain"}}"#,
+    );
+    let output_config = output_config(LogFormat::Long);
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    HTTP/1.1 200 OK\n",
+            "    set-cookie: a=1\n",
+            "    set-cookie: b=2\n",
+            "    content-type: text/plain\n",
+        )
+    );
+}
+
+#[test]
+fn control_characters_are_escaped_when_requested() {
+    let input = SIMPLE_LINE
+        .replace("My message", r"before\u0000after")
+        .replace(r#""v":0"#, r#""v":0,"out":"\u001b[31mred\u001b[0m""#);
+    let config = LoggerOutputConfig {
+        escape_control_chars: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: before\\x00after \
+         (out=\\x1b[31mred\\x1b[0m)\n"
+    );
+
+    let config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: before\0after \
+         (out=\u{1b}[31mred\u{1b}[0m)\n"
+    );
+}
+
+#[test]
+fn duplicate_keys_keep_the_value_chosen_by_the_policy() {
+    let input = SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"msg":"Second message""#);
+
+    let output_config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: Second message\n"
+    );
+
+    let output_config = LoggerOutputConfig {
+        duplicate_keys: DuplicateKeys::FirstWins,
+        ..output_config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n"
+    );
+}
+
+#[test]
+fn duplicate_keys_policy_applies_to_json_array() {
+    let input = SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"msg":"Second message""#);
+    let output_config = LoggerOutputConfig {
+        duplicate_keys: DuplicateKeys::FirstWins,
+        ..output_config(LogFormat::JsonArray(0))
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!("[{}]\n", SIMPLE_LINE)
+    );
+}
+
+#[test]
+fn duplicate_keys_policy_applies_to_merge_window() {
+    // Lines are ordered by the first of their duplicated times
+    let input = [
+        SIMPLE_LINE
+            .replace("My message", "A")
+            .replace(r#""v":0"#, r#""v":0,"time":"2012-02-08T22:56:50.000Z""#),
+        SIMPLE_LINE.replace("My message", "B").replace(
+            r#""time":"2012-02-08T22:56:52.856Z""#,
+            r#""time":"2012-02-08T22:56:51.000Z""#,
+        ),
+    ]
+    .join("\n");
+    let output_config = LoggerOutputConfig {
+        merge_window: 2,
+        duplicate_keys: DuplicateKeys::FirstWins,
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - B\nINFO - A\n"
+    );
+}
+
+#[test]
+fn line_prefix_is_written_at_the_start_of_each_line() {
+    let input = SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"stack":"first\nsecond""#);
+
+    for prefix in ["a.log: ", "b.log: "] {
+        let output_config = LoggerOutputConfig {
+            line_prefix: Some(prefix.to_string()),
+            ..output_config(LogFormat::Long)
+        };
+
+        let actual = write_output(
+            format!("{}\n{}", input, SIMPLE_LINE).as_bytes(),
+            &output_config,
+        );
+        let expected: String = [
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    stack: first\n",
+            "    second\n",
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+        ]
+        .iter()
+        .map(|line| format!("{}{}", prefix, line))
+        .collect();
+
+        assert_eq!(actual, expected);
+    }
+}
+
+fn req_body_line(body: &str) -> String {
+    SIMPLE_LINE.replace(
+        r#""v":0"#,
+        &format!(
+            r#""v":0,"req":{{"method":"POST","url":"/path","body":{}}}"#,
+            serde_json::Value::from(body)
+        ),
+    )
+}
+
+#[test]
+fn pretty_json_bodies_pretty_prints_json_string_bodies() {
+    let config = LoggerOutputConfig {
+        pretty_json_bodies: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(req_body_line(r#"{"a":1}"#).as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    POST /path HTTP/1.1\n",
+            "    {\n",
+            "      \"a\": 1\n",
+            "    }\n",
+        )
+    );
+
+    let config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(req_body_line(r#"{"a":1}"#).as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    POST /path HTTP/1.1\n",
+            "    {\"a\":1}\n",
+        )
+    );
+}
+
+#[test]
+fn pretty_json_bodies_keeps_plain_text_bodies_raw() {
+    let output_config = LoggerOutputConfig {
+        pretty_json_bodies: true,
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(req_body_line("not {json}").as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    POST /path HTTP/1.1\n",
+            "    not {json}\n",
+        )
+    );
+}
+
+#[test]
+fn fail_fast_returns_the_error_of_the_first_malformed_line() {
+    let input = format!("{}\nnot json\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    let output_config = LoggerOutputConfig {
+        fail_fast: true,
+        ..output_config(LogFormat::Simple)
+    };
+
+    let mut writer: Vec<u8> = Vec::new();
+    let error = bunyan_view::write_bunyan_output(&mut writer, input.as_bytes(), &output_config)
+        .expect_err("malformed line should stop the output");
+
+    assert!(
+        matches!(error, ViewError::ParseError { line_no: 2, .. }),
+        "{:?}",
+        error
+    );
+    assert_eq!(String::from_utf8(writer).unwrap(), "INFO - My message\n");
+}
+
+#[test]
+fn fail_fast_writes_all_valid_lines() {
+    let input = format!("{}\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    let output_config = LoggerOutputConfig {
+        fail_fast: true,
+        ..output_config(LogFormat::Simple)
+    };
+
+    let mut writer: Vec<u8> = Vec::new();
+    let result = bunyan_view::write_bunyan_output(&mut writer, input.as_bytes(), &output_config);
+
+    assert!(result.is_ok());
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "INFO - My message\nINFO - My message\n"
+    );
+}
+
+#[test]
+fn fixed_time_width_aligns_relative_times() {
+    let input = ["2012-02-08T22:56:52.856Z", "2012-02-08T22:57:04.990Z"]
+        .iter()
+        .map(|time| SIMPLE_LINE.replace("2012-02-08T22:56:52.856Z", time))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let config = LoggerOutputConfig {
+        relative_time: true,
+        fixed_time_width: true,
+        ..output_config(LogFormat::Short)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "    +0.000  INFO myservice: My message\n",
+            "   +12.134  INFO myservice: My message\n",
+        )
+    );
+
+    let config = LoggerOutputConfig {
+        fixed_time_width: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "[2012-02-08T22:57:04.990Z]  INFO: myservice/123 on example.com: My message\n",
+        )
+    );
+}
+
+#[test]
+fn filters_passing_every_line_leave_output_unchanged() {
+    let input = format!(
+        "{}\n{}\nnot json\n{}",
+        SIMPLE_LINE,
+        SIMPLE_LINE.replace("\"level\":30", "\"level\":10"),
+        SIMPLE_LINE.replace("\"msg\":\"My message\"", "\"msg\":\"Other message\"")
+    );
+
+    let unfiltered = write_output(input.as_bytes(), &output_config(LogFormat::Long));
+    let filtered = write_output(
+        input.as_bytes(),
+        &LoggerOutputConfig {
+            level: Some(0),
+            condition_filter: Some(ConditionFilter::new("this.pid === 123")),
+            ..output_config(LogFormat::Long)
+        },
+    );
+
+    assert_eq!(filtered, unfiltered);
+    assert_eq!(unfiltered.matches("My message").count(), 2);
+}
+
+#[test]
+fn err_message_is_written_once_when_stack_starts_with_it() {
+    let input = SIMPLE_LINE.replace(
+        "\"v\":0",
+        r#""v":0,"err":{"message":"boom","name":"Error","stack":"Error: boom\n    at main (app.js:1:1)"}"#,
+    );
+
+    let actual = write_output(input.as_bytes(), &output_config(LogFormat::Long));
+    assert!(
+        actual.ends_with("My message\n    Error: boom\n        at main (app.js:1:1)\n"),
+        "{:?}",
+        actual
+    );
+}
+
+#[test]
+fn err_message_is_written_before_stack_without_it() {
+    let input = SIMPLE_LINE.replace(
+        "\"v\":0",
+        r#""v":0,"err":{"message":"boom","name":"Error","stack":"    at main (app.js:1:1)"}"#,
+    );
+
+    let actual = write_output(input.as_bytes(), &output_config(LogFormat::Long));
+    assert!(
+        actual.ends_with("My message\n    Error: boom\n        at main (app.js:1:1)\n"),
+        "{:?}",
+        actual
+    );
+}
+
+fn fail_fast_error(input: &str, output_config: LoggerOutputConfig) -> ViewError {
+    let output_config = LoggerOutputConfig {
+        fail_fast: true,
+        ..output_config
+    };
+
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_output(&mut writer, input.as_bytes(), &output_config)
+        .expect_err("invalid line should stop the output")
+}
+
+#[test]
+fn view_error_reports_malformed_lines_as_parse_errors() {
+    let input = format!(
+        "{}
+{{\"msg\":",
+        SIMPLE_LINE
+    );
+
+    match fail_fast_error(&input, output_config(LogFormat::Simple)) {
+        ViewError::ParseError {
+            line_no, column, ..
+        } => {
+            assert_eq!(line_no, 2);
+            assert_eq!(column, Some(7));
+        }
+        error => panic!("unexpected error: {:?}", error),
+    }
+}
+
+#[test]
+fn view_error_reports_missing_required_fields_as_validation_errors() {
+    let output_config = LoggerOutputConfig {
+        require_fields: vec!["env".to_string()],
+        ..output_config(LogFormat::Simple)
+    };
+
+    match fail_fast_error(SIMPLE_LINE, output_config) {
+        ViewError::ValidationError { message, line_no } => {
+            assert_eq!(message, "missing required field `env`");
+            assert_eq!(line_no, 1);
+        }
+        error => panic!("unexpected error: {:?}", error),
+    }
+}
+
+#[test]
+fn view_error_wraps_io_errors() {
+    let error = ViewError::from(std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "pipe closed",
+    ));
+
+    match error {
+        ViewError::IoError(ref e) => assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe),
+        ref error => panic!("unexpected error: {:?}", error),
+    }
+    assert!(std::error::Error::source(&error).is_some());
+    assert_eq!(error.to_string(), "pipe closed");
+}
+
+#[test]
+fn stable_inspect_output_ignores_key_order() {
+    let input = r#"{"name":"myservice","pid":123,"hostname":"example.com","level":30,"msg":"My message","time":"2012-02-08T22:56:52.856Z","v":0,"req":{"url":"/","method":"GET"},"tags":["b","a"],"empty":{}}"#;
+    let shuffled = r#"{"empty":{},"tags":["b","a"],"v":0,"time":"2012-02-08T22:56:52.856Z","req":{"method":"GET","url":"/"},"msg":"My message","level":30,"hostname":"example.com","pid":123,"name":"myservice"}"#;
+
+    let output_config = LoggerOutputConfig {
+        stable_inspect: true,
+        ..output_config(LogFormat::Inspect)
+    };
+    let expected = concat!(
+        "{\n",
+        "  empty: {},\n",
+        "  hostname: 'example.com',\n",
+        "  level: 30,\n",
+        "  msg: 'My message',\n",
+        "  name: 'myservice',\n",
+        "  pid: 123,\n",
+        "  req: {\n",
+        "    method: 'GET',\n",
+        "    url: '/'\n",
+        "  },\n",
+        "  tags: [\n",
+        "    'b',\n",
+        "    'a'\n",
+        "  ],\n",
+        "  time: '2012-02-08T22:56:52.856Z',\n",
+        "  v: 0\n",
+        "}\n",
+    );
+
+    assert_eq!(write_output(input.as_bytes(), &output_config), expected);
+    assert_eq!(write_output(shuffled.as_bytes(), &output_config), expected);
+}
+
+#[test]
+fn pid_filter_only_writes_lines_of_that_process() {
+    let input = [(100, "one"), (200, "two"), (300, "three"), (200, "four")]
+        .iter()
+        .map(|(pid, msg)| {
+            SIMPLE_LINE
+                .replace("\"pid\":123", &format!("\"pid\":{}", pid))
+                .replace("My message", msg)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let output_config = LoggerOutputConfig {
+        pid_filter: Some(200),
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - two\nINFO - four\n"
+    );
+
+    let output_config = LoggerOutputConfig {
+        pid_filter: Some(123),
+        ..output_config
+    };
+    assert_eq!(write_output(input.as_bytes(), &output_config), "");
+}
+
+#[test]
+fn time_as_epoch_writes_epoch_millis() {
+    for (format, expected) in [
+        (
+            LogFormat::Long,
+            "[1328741812856]  INFO: myservice/123 on example.com: My message\n",
+        ),
+        (
+            LogFormat::Short,
+            "1328741812856  INFO myservice: My message\n",
+        ),
+    ] {
+        let output_config = LoggerOutputConfig {
+            time_as_epoch: true,
+            display_local_time: true,
+            ..output_config(format)
+        };
+
+        assert_eq!(
+            write_output(SIMPLE_LINE.as_bytes(), &output_config),
+            expected
+        );
+    }
+}
+
+#[test]
+fn err_without_stack_is_written_with_all_of_its_fields() {
+    let input = SIMPLE_LINE.replace(
+        "\"v\":0",
+        r#""v":0,"err":{"message":"not found","code":"ENOENT","statusCode":404}"#,
+    );
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config(LogFormat::Long)),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message ",
+            "(err.message=\"not found\", err.code=ENOENT, err.statusCode=404)\n",
+        )
+    );
+}
+
+#[test]
+fn count_only_writes_the_number_of_filtered_lines() {
+    let input = [30, 50, 10, 50, 40]
+        .iter()
+        .map(|level| SIMPLE_LINE.replace("\"level\":30", &format!("\"level\":{}", level)))
+        .chain(std::iter::once("not json".to_string()))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let output_config = LoggerOutputConfig {
+        count_only: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(write_output(input.as_bytes(), &output_config), "5\n");
+
+    let output_config = LoggerOutputConfig {
+        level: Some(50),
+        ..output_config
+    };
+    assert_eq!(write_output(input.as_bytes(), &output_config), "2\n");
+}
+
+#[test]
+fn count_only_does_not_count_malformed_lines() {
+    let input = format!("{}\nnot json\n{{\"a\":\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+
+    for format in [LogFormat::Json(0), LogFormat::Json(2), LogFormat::Long] {
+        let output_config = LoggerOutputConfig {
+            count_only: true,
+            max_line_bytes: Some(1),
+            ..output_config(format)
+        };
+        assert_eq!(write_output(input.as_bytes(), &output_config), "2\n");
+    }
+
+    let output_config = LoggerOutputConfig {
+        limit: Some(1),
+        ..output_config(LogFormat::Json(0))
+    };
+    assert_eq!(
+        write_output(
+            format!("not json\n{}\n", SIMPLE_LINE).as_bytes(),
+            &output_config
+        ),
+        format!("not json\n{}\n", SIMPLE_LINE)
+    );
+}
+
+#[test]
+fn crlf_line_ending_ends_every_line_of_output() {
+    let input = format!("{}\n{}\n", req_url_line("/path"), SIMPLE_LINE);
+    let config = LoggerOutputConfig {
+        line_ending: LineEnding::CrLf,
+        ..output_config(LogFormat::Long)
+    };
+
+    let expected = write_output(input.as_bytes(), &output_config(LogFormat::Long));
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        expected.replace('\n', "\r\n")
+    );
+
+    let config = LoggerOutputConfig {
+        line_ending: LineEnding::CrLf,
+        no_trailing_newline: true,
+        ..output_config(LogFormat::Simple)
+    };
+    let input = format!("{}\n{}\n", SIMPLE_LINE, SIMPLE_LINE);
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "INFO - My message\r\nINFO - My message"
+    );
+}
+
+#[test]
+fn crlf_line_ending_keeps_existing_crlf() {
+    let input = SIMPLE_LINE.replace("My message", "first\\r\\nsecond");
+    let config = LoggerOutputConfig {
+        line_ending: LineEnding::CrLf,
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "INFO - first\r\nsecond\r\n"
+    );
+
+    let line: BunyanLine = serde_json::from_str(&input).unwrap();
+    assert_eq!(
+        bunyan_view::format_line_to_string(&line, &config).unwrap(),
+        "INFO - first\r\nsecond\r\n"
+    );
+}
+
+#[test]
+fn trace_context_ids_are_truncated_when_requested() {
+    let input = [
+        r#""trace_id":"4bf92f3577b34da6a3ce929d0e0e4736","span_id":"00f067aa0ba902b7""#,
+        r#""traceId":"4bf92f3577b34da6a3ce929d0e0e4736","spanId":"00f067aa0ba902b7""#,
+    ]
+    .iter()
+    .map(|ids| SIMPLE_LINE.replace("\"v\":0", &format!("\"v\":0,{}", ids)))
+    .collect::<Vec<String>>()
+    .join("\n");
+
+    let output_config = LoggerOutputConfig {
+        truncate_trace_ids: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (trace_id=4bf92f35, span_id=00f067aa)\n",
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (traceId=4bf92f35, spanId=00f067aa)\n",
+        )
+    );
+
+    let output_config = LoggerOutputConfig {
+        truncate_trace_ids: false,
+        ..output_config
+    };
+    assert!(write_output(input.as_bytes(), &output_config)
+        .contains("(trace_id=4bf92f3577b34da6a3ce929d0e0e4736, span_id=00f067aa0ba902b7)"));
+}
+
+#[test]
+fn diff_mode_displays_only_changed_fields() {
+    let input = [
+        r#""state":"idle","retries":0"#,
+        r#""state":"running","retries":0"#,
+    ]
+    .iter()
+    .map(|fields| SIMPLE_LINE.replace("\"v\":0", &format!("\"v\":0,{}", fields)))
+    .collect::<Vec<String>>()
+    .join("\n");
+
+    let output_config = LoggerOutputConfig {
+        diff_mode: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (state=idle, retries=0)\n",
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (state=running)\n",
+        )
+    );
+}
+
+#[test]
+fn config_is_read_from_toml() {
+    let config = bunyan_view::config_from_toml(
+        r#"
+        level = 40
+        format = { json = 2 }
+        line_ending = "crlf"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.level, Some(40));
+    assert_eq!(config.format, LogFormat::Json(2));
+    assert_eq!(config.line_ending, LineEnding::CrLf);
+    // Fields missing from the file keep their default value
+    assert_eq!(config.indent, LoggerOutputConfig::default().indent);
+}
+
+#[test]
+fn invalid_toml_config_is_an_error() {
+    assert!(bunyan_view::config_from_toml("level = \"warn\"").is_err());
+    assert!(bunyan_view::config_from_toml("format = \"xml\"").is_err());
+}
+
+#[test]
+fn long_format_time_brackets_can_be_omitted() {
+    let config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n"
+    );
+
+    let config = LoggerOutputConfig {
+        time_brackets: false,
+        ..config
+    };
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &config),
+        "2012-02-08T22:56:52.856Z  INFO: myservice/123 on example.com: My message\n"
+    );
+}
+
+#[test]
+fn context_field_keys_are_displayed_as_params() {
+    let input = SIMPLE_LINE.replace(
+        "\"v\":0",
+        r#""v":0,"user":"alice","context":{"order":42,"user":"bob"}"#,
+    );
+
+    let config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (user=alice)\n",
+            "    context: {\n",
+            "      \"order\": 42,\n",
+            "      \"user\": \"bob\"\n",
+            "    }\n",
+        )
+    );
+
+    let config = LoggerOutputConfig {
+        context_field: Some("context".to_string()),
+        ..config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (user=alice, order=42)\n",
+            "    context: {\n",
+            "      \"user\": \"bob\"\n",
+            "    }\n",
+        )
+    );
+}
+
+#[test]
+fn lines_without_time_follow_time_fallback() {
+    let input = SIMPLE_LINE.replace(r#""time":"2012-02-08T22:56:52.856Z","#, "");
+
+    let config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        format!("{}\n", input)
+    );
+
+    let config = LoggerOutputConfig {
+        time_fallback: TimeFallback::Unknown,
+        ..config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "[?]  INFO: myservice/123 on example.com: My message\n"
+    );
+
+    let config = LoggerOutputConfig {
+        time_fallback: TimeFallback::Now,
+        ..config
+    };
+    let output = write_output(input.as_bytes(), &config);
+    let time = output[1..output.find(']').unwrap()]
+        .parse::<chrono::DateTime<chrono::Utc>>()
+        .unwrap();
+    assert!((chrono::Utc::now() - time).num_seconds() < 60);
+    assert!(output.ends_with("]  INFO: myservice/123 on example.com: My message\n"));
+}
+
+#[test]
+fn fallback_time_is_left_out_of_json() {
+    let input = SIMPLE_LINE.replace(r#""time":"2012-02-08T22:56:52.856Z","#, "");
+    let config = LoggerOutputConfig {
+        time_fallback: TimeFallback::Now,
+        ..output_config(LogFormat::Gelf)
+    };
+
+    let mut json: Vec<serde_json::Value> = Vec::new();
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_output_with(&mut writer, input.as_bytes(), &config, |line| {
+        json.push(line.to_json())
+    })
+    .unwrap();
+
+    assert_eq!(json.len(), 1);
+    assert_eq!(json[0].get("time"), None);
+    assert_eq!(json[0]["msg"], "My message");
+
+    let gelf: serde_json::Value = serde_json::from_slice(&writer).unwrap();
+    assert_eq!(gelf.get("timestamp"), None);
+    assert_eq!(gelf["short_message"], "My message");
+}
+
+#[test]
+fn req_socket_is_written_with_request() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"req":{"method":"GET","url":"/path","headers":{"host":"example.com"},"socket":{"remoteAddress":"10.0.0.1","bytesRead":512}}"#,
+    );
+    let config = output_config(LogFormat::Long);
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    GET /path HTTP/1.1\n",
+            "    host: example.com\n",
+            "    socket: {\n",
+            "      \"remoteAddress\": \"10.0.0.1\",\n",
+            "      \"bytesRead\": 512\n",
+            "    }\n",
+        )
+    );
+}
+
+#[test]
+fn msg_whitespace_is_collapsed_when_requested() {
+    let input = SIMPLE_LINE.replace("My message", r"My\n\tmulti  line\tmessage");
+
+    let config = LoggerOutputConfig {
+        collapse_msg_whitespace: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My multi line message\n"
+    );
+
+    let config = LoggerOutputConfig {
+        collapse_msg_whitespace: false,
+        ..config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com\n",
+            "        My\n",
+            "    \tmulti  line\tmessage\n",
+        )
+    );
+}
+
+#[test]
+fn debug_errors_are_written_inline_when_requested() {
+    let input = format!("{}\nnot json\n", SIMPLE_LINE);
+    let config = LoggerOutputConfig {
+        is_debug: true,
+        errors_inline: true,
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "INFO - My message\n# expected ident at on line 2 column: 2\nnot json\n"
+    );
+}
+
+#[test]
+fn wide_long_format_header_is_wrapped_when_requested() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"src":{"file":"/srv/app/lib/handler.js","line":42}"#,
+    );
+
+    let config = LoggerOutputConfig {
+        wrap_header: true,
+        terminal_width: Some(80),
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123\n",
+            "    on example.com (/srv/app/lib/handler.js:42): My message\n",
+        )
+    );
+
+    let config = LoggerOutputConfig {
+        terminal_width: Some(200),
+        ..config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com \
+         (/srv/app/lib/handler.js:42): My message\n"
+    );
+}
+
+#[test]
+fn latency_percentiles_are_written_after_the_lines() {
+    let mut lines: Vec<String> = (1..=20)
+        .map(|latency| {
+            SIMPLE_LINE.replace(
+                r#""v":0"#,
+                &format!(r#""v":0,"responseTime":{}"#, latency * 5),
+            )
+        })
+        .collect();
+    lines.push(SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"responseTime":"fast""#));
+    lines.push(SIMPLE_LINE.to_string());
+    let input = lines.join("\n");
+
+    let config = LoggerOutputConfig {
+        latency_summary: Some("responseTime".to_string()),
+        count_only: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "22\nresponseTime latency: p50=50 p90=90 p99=100 (20 values)\n"
+    );
+
+    let config = LoggerOutputConfig {
+        latency_summary: Some("duration".to_string()),
+        ..config
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "22\nduration latency: no values\n"
+    );
+}
+
+#[test]
+fn relative_time_displays_offsets_from_anchor_time() {
+    let input = ["2012-02-08T22:56:52.856Z", "2012-02-08T22:56:54.090Z"]
+        .iter()
+        .map(|time| SIMPLE_LINE.replace("2012-02-08T22:56:52.856Z", time))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let anchor_time = "2012-02-08T22:56:53.000Z".parse().unwrap();
+    let config = LoggerOutputConfig {
+        relative_time: true,
+        anchor_time: Some(anchor_time),
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        concat!(
+            "[-0.144]  INFO: myservice/123 on example.com: My message\n",
+            "[+1.090]  INFO: myservice/123 on example.com: My message\n",
+        )
+    );
+}
+
+#[test]
+fn level_icons_are_written_before_levels() {
+    let input = SIMPLE_LINE.replace(r#""level":30"#, r#""level":50"#);
+    let config = LoggerOutputConfig {
+        level_icons: Some(LogLevel::default_icons()),
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "[2012-02-08T22:56:52.856Z] 🔴 ERROR: myservice/123 on example.com: My message\n"
+    );
+
+    let mut level_icons = HashMap::new();
+    level_icons.insert(LogLevel::WARN, "!".to_string());
+    let config = LoggerOutputConfig {
+        level_icons: Some(level_icons),
+        ..output_config(LogFormat::Simple)
+    };
+    let input = format!(
+        "{}\n{}",
+        input,
+        SIMPLE_LINE.replace(r#""level":30"#, r#""level":40"#)
+    );
+    assert_eq!(
+        write_output(input.as_bytes(), &config),
+        "ERROR - My message\n! WARN - My message\n"
+    );
+}
+
+#[test]
+fn json_indent_is_read_from_config_for_configured_indent() {
+    let explicit_output = write_output(SIMPLE_LINE.as_bytes(), &output_config(LogFormat::Json(4)));
+    assert!(
+        explicit_output.contains("\n    \"name\""),
+        "{:?}",
+        explicit_output
+    );
+
+    let config = LoggerOutputConfig {
+        json_indent: 4,
+        ..output_config(LogFormat::Json(LogFormat::CONFIGURED_INDENT))
+    };
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &config),
+        explicit_output
+    );
+
+    // An explicit indent takes precedence over the configured one
+    let config = LoggerOutputConfig {
+        format: LogFormat::Json(0),
+        ..config
+    };
+    assert_eq!(
+        write_output(SIMPLE_LINE.as_bytes(), &config),
+        format!("{}\n", SIMPLE_LINE)
+    );
+}
+
+#[test]
+fn req_headers_keep_their_order_and_case() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"req":{"method":"GET","url":"/path","headers":{"X-Request-Id":"abc","Content-Type":"text/plain","accept":"*/*"}}"#,
+    );
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config(LogFormat::Long)),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    GET /path HTTP/1.1\n",
+            "    X-Request-Id: abc\n",
+            "    Content-Type: text/plain\n",
+            "    accept: */*\n",
+        )
+    );
+}
+
+#[test]
+fn unique_msgs_displays_only_first_occurrences() {
+    let input = [
+        "Starting", "Retrying", "Starting", "Done", "Retrying", "Done",
+    ]
+    .iter()
+    .map(|msg| SIMPLE_LINE.replace("My message", msg))
+    .collect::<Vec<String>>()
+    .join("\n");
+
+    let output_config = LoggerOutputConfig {
+        unique_msgs: true,
+        ..output_config(LogFormat::Simple)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - Starting\nINFO - Retrying\nINFO - Done\n"
+    );
+}
+
+#[test]
+fn configured_req_detail_field_is_written_as_body() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"req":{"method":"POST","url":"/path","payload":{"id":1},"attempt":2}"#,
+    );
+    let output_config = LoggerOutputConfig {
+        req_detail_fields: Some(
+            ["method", "url", "payload"]
+                .iter()
+                .map(|field| field.to_string())
+                .collect(),
+        ),
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (req.attempt=2)\n",
+            "    POST /path HTTP/1.1\n",
+            "    {\n",
+            "      \"id\": 1\n",
+            "    }\n",
+        )
+    );
+}
+
+#[test]
+fn configured_res_detail_fields_replace_the_defaults() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"res":{"statusCode":200,"body":"ok","reply":"done"}"#,
+    );
+    let output_config = LoggerOutputConfig {
+        res_detail_fields: Some(vec!["statusCode".to_string(), "reply".to_string()]),
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (res.body=ok)\n",
+            "    HTTP/1.1 200 OK\n",
+            "    done\n",
+        )
+    );
+}
+
+#[test]
+fn configured_detail_fields_keep_the_summary_lines() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"req":{"method":"GET","url":"/path","payload":"hi"},"res":{"statusCode":404,"reply":"done"}"#,
+    );
+    let output_config = LoggerOutputConfig {
+        req_detail_fields: Some(vec!["payload".to_string()]),
+        res_detail_fields: Some(vec!["reply".to_string()]),
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+            "    GET /path HTTP/1.1\n",
+            "    hi\n",
+            "    --\n",
+            "    HTTP/1.1 404 Not Found\n",
+            "    done\n",
+        )
+    );
+}
+
+#[test]
+fn write_lines_renders_only_the_requested_range() {
+    let lines: Vec<BunyanLine> = ["first", "second", "third", "fourth"]
+        .iter()
+        .map(|msg| serde_json::from_str(&SIMPLE_LINE.replace("My message", msg)).unwrap())
+        .collect();
+    let output_config = output_config(LogFormat::Simple);
+
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_lines(&mut writer, &lines, 1..3, &output_config).unwrap();
+    assert_eq!(
+        String::from_utf8(writer).unwrap(),
+        "INFO - second\nINFO - third\n"
+    );
+
+    // Ranges past the end of the lines are clamped
+    let mut writer: Vec<u8> = Vec::new();
+    bunyan_view::write_lines(&mut writer, &lines, 3..10, &output_config).unwrap();
+    assert_eq!(String::from_utf8(writer).unwrap(), "INFO - fourth\n");
+}
+
+#[test]
+fn small_objects_are_compacted_inline() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"size":{"w":1,"h":2},"user":{"name":"someone","email":"someone@example.com"}"#,
+    );
+    let output_config = LoggerOutputConfig {
+        compact_small_objects: Some(20),
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        concat!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (size={\"w\":1,\"h\":2})\n",
+            "    user: {\n",
+            "      \"name\": \"someone\",\n",
+            "      \"email\": \"someone@example.com\"\n",
+            "    }\n",
+        )
+    );
+}
+
+#[test]
+fn render_writes_custom_formats() {
+    let line: BunyanLine = serde_json::from_str(SIMPLE_LINE).unwrap();
+
+    for (format, expected) in [
+        (
+            LogFormat::Long,
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n",
+        ),
+        (
+            LogFormat::Short,
+            "22:56:52.856Z  INFO myservice: My message\n",
+        ),
+        (LogFormat::Simple, "INFO - My message\n"),
+    ] {
+        let mut writer: Vec<u8> = Vec::new();
+        line.render(&mut writer, &output_config(format)).unwrap();
+        assert_eq!(String::from_utf8(writer).unwrap(), expected);
+    }
+
+    let mut writer: Vec<u8> = Vec::new();
+    line.render(&mut writer, &output_config(LogFormat::Gelf))
+        .unwrap();
+    let gelf: serde_json::Value = serde_json::from_slice(&writer).unwrap();
+    assert_eq!(gelf["short_message"], "My message");
+}
+
+#[test]
+fn render_returns_error_for_json_formats() {
+    let line: BunyanLine = serde_json::from_str(SIMPLE_LINE).unwrap();
+
+    for format in [
+        LogFormat::Json(2),
+        LogFormat::JsonArray(0),
+        LogFormat::Inspect,
+    ] {
+        let mut writer: Vec<u8> = Vec::new();
+        let result = line.render(&mut writer, &output_config(format));
+        assert!(
+            matches!(result, Err(ViewError::ValidationError { line_no: 1, .. })),
+            "{:?}",
+            result
+        );
+        assert!(writer.is_empty());
+    }
+}
+
+#[test]
+fn wide_mode_inlines_long_strings() {
+    let long_value = "x".repeat(100);
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        &format!(r#""v":0,"token":"{}","note":"a\nb""#, long_value),
+    );
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config(LogFormat::Long)),
+        format!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message\n    \
+             token: {}\n    --\n    note: a\n    b\n",
+            long_value
+        )
+    );
+
+    let output_config = LoggerOutputConfig {
+        wide: true,
+        ..output_config(LogFormat::Long)
+    };
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        format!(
+            "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+             (token={})\n    note: a\n    b\n",
+            long_value
+        )
+    );
+}
+
+#[test]
+fn configured_req_id_field_is_displayed_first() {
+    let input = SIMPLE_LINE.replace(
+        r#""v":0"#,
+        r#""v":0,"user":"someone","requestId":"abc","req_id":"other""#,
+    );
+    let output_config = LoggerOutputConfig {
+        req_id_field: Some("requestId".to_string()),
+        ..output_config(LogFormat::Long)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "[2012-02-08T22:56:52.856Z]  INFO: myservice/123 on example.com: My message \
+         (requestId=abc, user=someone, req_id=other)\n"
+    );
+}
+
+#[test]
+fn strict_mode_validates_the_configured_req_id_field() {
+    let valid = SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"requestId":"abc","req_id":[1]"#);
+    let invalid = SIMPLE_LINE.replace(r#""v":0"#, r#""v":0,"requestId":{"id":1}"#);
+    let input = format!("{}\n{}\n", valid, invalid);
+    let output_config = LoggerOutputConfig {
+        req_id_field: Some("requestId".to_string()),
+        is_strict: true,
+        ..output_config(LogFormat::Simple)
+    };
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config),
+        "INFO - My message\n"
+    );
+}
+
+#[test]
+fn split_output_routes_lines_by_level() {
+    let input = [
+        SIMPLE_LINE.replace("My message", "started"),
+        SIMPLE_LINE
+            .replace("My message", "failed")
+            .replace(r#""level":30"#, r#""level":50"#),
+        "not json".to_string(),
+        SIMPLE_LINE
+            .replace("My message", "slow")
+            .replace(r#""level":30"#, r#""level":40"#),
+        SIMPLE_LINE
+            .replace("My message", "crashed")
+            .replace(r#""level":30"#, r#""level":60"#),
+    ]
+    .join("\n");
+
+    let mut error_writer: Vec<u8> = Vec::new();
+    let mut normal_writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_split(
+        &mut error_writer,
+        &mut normal_writer,
+        input.as_bytes(),
+        &output_config(LogFormat::Simple),
+        50,
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(error_writer).unwrap(),
+        "ERROR - failed\nFATAL - crashed\n"
+    );
+    assert_eq!(
+        String::from_utf8(normal_writer).unwrap(),
+        "INFO - started\nnot json\nWARN - slow\n"
+    );
+}
+
+#[test]
+fn split_output_prefixes_the_lines_of_both_writers() {
+    let input = [
+        SIMPLE_LINE.replace("My message", "started"),
+        SIMPLE_LINE
+            .replace("My message", "failed")
+            .replace(r#""level":30"#, r#""level":50"#),
+    ]
+    .join("\n");
+    let output_config = LoggerOutputConfig {
+        line_prefix: Some("> ".to_string()),
+        ..output_config(LogFormat::Simple)
+    };
+
+    let mut error_writer: Vec<u8> = Vec::new();
+    let mut normal_writer: Vec<u8> = Vec::new();
+    bunyan_view::write_bunyan_split(
+        &mut error_writer,
+        &mut normal_writer,
+        input.as_bytes(),
+        &output_config,
+        50,
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(error_writer).unwrap(),
+        "> ERROR - failed\n"
+    );
+    assert_eq!(
+        String::from_utf8(normal_writer).unwrap(),
+        "> INFO - started\n"
+    );
+}
+
+/// Writer whose writes always fail like a closed pipe
+struct BrokenPipeWriter;
+
+impl Write for BrokenPipeWriter {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::ErrorKind::BrokenPipe.into())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn split_output_returns_write_errors() {
+    let mut normal_writer: Vec<u8> = Vec::new();
+    let result = bunyan_view::write_bunyan_split(
+        &mut BrokenPipeWriter,
+        &mut normal_writer,
+        SIMPLE_LINE
+            .replace(r#""level":30"#, r#""level":50"#)
+            .as_bytes(),
+        &output_config(LogFormat::Simple),
+        50,
+    );
+
+    match result {
+        Err(ViewError::IoError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn split_output_rejects_buffered_outputs() {
+    let output_config = LoggerOutputConfig {
+        group_by: Some("hostname".to_string()),
+        ..output_config(LogFormat::Simple)
+    };
+
+    let mut error_writer: Vec<u8> = Vec::new();
+    let mut normal_writer: Vec<u8> = Vec::new();
+    let result = bunyan_view::write_bunyan_split(
+        &mut error_writer,
+        &mut normal_writer,
+        SIMPLE_LINE.as_bytes(),
+        &output_config,
+        50,
+    );
+
+    assert!(result.is_err());
+    assert!(error_writer.is_empty());
+    assert!(normal_writer.is_empty());
+}
+
+#[test]
+fn non_string_msg_is_displayed_as_json() {
+    let input = [
+        SIMPLE_LINE.replace(r#""My message""#, "42"),
+        SIMPLE_LINE.replace(r#""My message""#, r#"{"code":7,"text":"boom"}"#),
+    ]
+    .join("\n");
+
+    assert_eq!(
+        write_output(input.as_bytes(), &output_config(LogFormat::Simple)),
+        "INFO - 42\nINFO - {\"code\":7,\"text\":\"boom\"}\n"
+    );
+}
+
+#[test]
+fn parse_all_splits_lines_and_errors() {
+    let input = [
+        SIMPLE_LINE.replace("My message", "first"),
+        "not json".to_string(),
+        String::new(),
+        SIMPLE_LINE.replace("My message", "second"),
+        r#"{"msg":"missing fields"}"#.to_string(),
+    ]
+    .join("\n");
+
+    let (lines, errors) = bunyan_view::parse_all(input.as_bytes());
+
+    let messages: Vec<serde_json::Value> = lines
+        .iter()
+        .map(|line| line.to_json()["msg"].clone())
+        .collect();
+    assert_eq!(messages, vec!["first", "second"]);
+
+    let error_lines: Vec<usize> = errors
+        .iter()
+        .map(|error| match error {
+            ViewError::ParseError { line_no, .. } => *line_no,
+            other => panic!("unexpected error {:?}", other),
+        })
+        .collect();
+    assert_eq!(error_lines, vec![2, 5]);
+}
+
+/// Reader whose reads always fail
+struct BrokenReader;
+
+impl Read for BrokenReader {
+    fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("disk unplugged"))
+    }
+}
+
+#[test]
+fn parse_all_returns_read_errors() {
+    let input = format!("{}\n", SIMPLE_LINE);
+    let reader = BufReader::new(input.as_bytes().chain(BrokenReader));
+
+    let (lines, errors) = bunyan_view::parse_all(reader);
+
+    assert_eq!(lines.len(), 1);
+    assert!(
+        matches!(errors[..], [ViewError::IoError(_)]),
+        "{:?}",
+        errors
+    );
+}